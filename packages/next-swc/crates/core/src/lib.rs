@@ -88,6 +88,14 @@ pub struct TransformOptions {
     #[serde(default)]
     pub server_components: Option<react_server_components::Config>,
 
+    /// Don't warn about deprecated forms of the transform configs, e.g. a
+    /// boolean `server_components`. Each warning is only emitted once per
+    /// process, not once per compilation. That's deliberate: the warning only
+    /// has to reach the user once, and a long running `next dev` would repeat
+    /// it for every rebuild otherwise.
+    #[serde(default)]
+    pub disable_deprecation_warnings: bool,
+
     #[serde(default)]
     pub styled_components: Option<styled_components::Config>,
 
@@ -143,7 +151,11 @@ where
             Some(config) if config.truthy() =>
                 Either::Left(react_server_components::server_components(
                     file.name.clone(),
-                    config.clone(),
+                    if opts.disable_deprecation_warnings {
+                        react_server_components::Config::WithOptions(config.clone().into_options())
+                    } else {
+                        config.clone()
+                    },
                     comments.clone(),
                 )),
             _ => Either::Right(noop()),
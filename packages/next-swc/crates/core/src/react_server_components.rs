@@ -1,20 +1,37 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::Path,
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use regex::Regex;
 use serde::Deserialize;
 
 use swc_core::{
     common::{
-        comments::{Comment, CommentKind, Comments},
+        collections::{AHashMap, AHashSet},
+        comments::{Comment, CommentKind, Comments, SingleThreadedComments},
         errors::HANDLER,
-        FileName, Span, DUMMY_SP,
+        sync::Lrc,
+        BytePos, FileName, SourceMap, Span, Spanned, DUMMY_SP,
     },
     ecma::ast::*,
     ecma::atoms::{js_word, JsWord},
-    ecma::utils::{prepend_stmts, quote_ident, quote_str, ExprFactory},
-    ecma::visit::{as_folder, noop_visit_mut_type, Fold, VisitMut, VisitMutWith},
+    ecma::parser::parse_file_as_script,
+    ecma::utils::{find_pat_ids, prepend_stmts, quote_ident, quote_str, DropSpan, ExprFactory},
+    ecma::visit::{
+        as_folder, noop_visit_mut_type, noop_visit_type, Fold, Visit, VisitMut, VisitMutWith,
+        VisitWith,
+    },
 };
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(untagged)]
 pub enum Config {
+    /// Deprecated, warned about once per process unless
+    /// `disable_deprecation_warnings` is set.
     All(bool),
     WithOptions(Options),
 }
@@ -26,61 +43,803 @@ impl Config {
             Config::WithOptions(_) => true,
         }
     }
+
+    /// The options equivalent to this config. `Config::All` is the same as
+    /// the Server Components compilation with the default options.
+    pub fn into_options(self) -> Options {
+        match self {
+            Config::WithOptions(options) => options,
+            Config::All(_) => Options {
+                is_server: true,
+                ..Default::default()
+            },
+        }
+    }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+// Node.js builtins without a browser polyfill.
+const NODE_ONLY_BUILTINS: &[&str] = &[
+    "async_hooks",
+    "child_process",
+    "cluster",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "fs",
+    "http2",
+    "inspector",
+    "module",
+    "net",
+    "perf_hooks",
+    "readline",
+    "repl",
+    "tls",
+    "trace_events",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+];
+
+// Whether the `Config::All` deprecation was reported already, so that it's
+// only reported once per process instead of for every module. There's no
+// state shared by the modules of a compilation to scope it to, a long running
+// `next dev` therefore only reports it once.
+static CONFIG_ALL_DEPRECATION_REPORTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Options {
     pub is_server: bool,
+    /// Sources that are still allowed in the Client Components compilation but
+    /// should rather be imported from the server. Importing one of them emits a
+    /// warning instead of an error.
+    #[serde(default)]
+    pub discouraged_client_imports: Option<Vec<String>>,
+    /// The runtime the Server Components compilation targets.
+    #[serde(default)]
+    pub runtime: Option<Runtime>,
+    /// Module the generated client entry proxy is required from. Defaults to
+    /// `private-next-rsc-mod-ref-proxy`.
+    #[serde(default)]
+    pub proxy_module: Option<String>,
+    /// Overrides `proxy_module` when targeting the edge runtime.
+    #[serde(default)]
+    pub edge_proxy_module: Option<String>,
+    /// Files to skip entirely. Each entry is either a path prefix or a glob in
+    /// which `*` matches any sequence of characters.
+    #[serde(default)]
+    pub exclude: Option<Vec<String>>,
+    /// Emit a client entry proxy that defers requiring the proxy module until
+    /// a property of `module.exports` is first accessed.
+    #[serde(default)]
+    pub lazy: bool,
+    /// Emit the client entry proxy as an ES module instead of CommonJS. The
+    /// named exports of the module are re-exported from the proxy. Takes
+    /// precedence over `lazy`.
+    #[serde(default)]
+    pub esm: bool,
+    /// Import sources to resolve before the ban lists are checked, e.g. a
+    /// tsconfig path alias `@/react-dom-server` pointing to `react-dom/server`.
+    /// Only the resolved source is checked, so an alias to a banned source is
+    /// reported and a banned source aliased to an allowed one isn't.
+    #[serde(default)]
+    pub alias_map: Option<HashMap<String, String>>,
+    /// React and ReactDOM APIs that are allowed in the Server Components
+    /// compilation when imported from a specific source, keyed by source.
+    #[serde(default)]
+    pub allowed_server_specifiers: Option<HashMap<String, Vec<String>>>,
+    /// Report React Hooks called at the top level of a module in the Client
+    /// Components compilation, which is always a bug.
+    #[serde(default)]
+    pub check_top_level_hooks: bool,
+    /// End the directive prologue at the first string literal that isn't a
+    /// known directive, instead of skipping over it.
+    #[serde(default)]
+    pub strict_directive_prologue: bool,
+    /// Sources that can't be imported by Server Actions modules, on top of the
+    /// ones disallowed in the Server Components compilation.
+    #[serde(default)]
+    pub invalid_action_imports: Option<Vec<String>>,
+    /// Sources known to be client entries. Server modules importing them are
+    /// recorded in the analysis so that the consumer can verify the imports go
+    /// through the client entry proxy.
+    #[serde(default)]
+    pub client_entry_sources: Option<Vec<String>>,
+    /// Sources known to be Server Components. Client modules can't import
+    /// them.
+    #[serde(default)]
+    pub server_entry_sources: Option<Vec<String>>,
+    /// Record the React Hooks imported by each module in the analysis.
+    #[serde(default)]
+    pub collect_hook_usage: bool,
+    /// Sources to check like `react` and `react-dom`, e.g. `preact/compat`.
+    #[serde(default)]
+    pub react_aliases: Option<Vec<String>>,
+    /// Statements to add to the client entry proxy before the proxy module is
+    /// required, e.g. for instrumentation in development.
+    #[serde(default)]
+    pub proxy_prelude: Option<Vec<String>>,
+    /// Disallow Node.js builtins in the Client Components compilation. Every
+    /// `node:` import is reported, bare imports only for the builtins that
+    /// can't be polyfilled in the browser, e.g. `fs` but not `buffer`.
+    #[serde(default)]
+    pub ban_node_builtins_on_client: bool,
+    /// Treat the module as a client entry if `"use client"` is anywhere in its
+    /// top level statements, not only in the directive prologue. For legacy
+    /// code bases.
+    #[serde(default)]
+    pub lenient_directive_position: bool,
+    /// Severity of disallowed imports and APIs. Defaults to `error`.
+    #[serde(default)]
+    pub severity: Option<Severity>,
+    /// Overrides `severity` for specific sources, e.g. to only warn about an
+    /// internal package that is being migrated.
+    #[serde(default)]
+    pub source_severity: Option<HashMap<String, Severity>>,
+    /// Also mark client entry proxies with an
+    /// `export const __next_rsc_client_entry__ = true;`, for consumers that
+    /// can't rely on the marker comment surviving minification. Requires
+    /// `esm`, the CommonJS proxies can't be marked.
+    #[serde(default)]
+    pub marker_as_export: bool,
+    /// Packages of the React Server Components runtime that user code in the
+    /// Server Components compilation can't import, including their subpaths.
+    /// Defaults to `react-server-dom-webpack`, bundlers with their own runtime
+    /// package can swap it.
+    #[serde(default)]
+    pub internal_packages: Option<Vec<String>>,
+    /// Warn when the default export of a client entry is a literal, object or
+    /// array instead of a component, which is usually a mistake.
+    #[serde(default)]
+    pub warn_non_component_client_default: bool,
+    /// Extensions of the files whose client entries are rewritten to a module
+    /// reference, e.g. `["js", "tsx"]`. Defaults to all of them. Client entries
+    /// of other files keep their code and their directive, and are checked
+    /// like any other module of the Server Components compilation.
+    #[serde(default)]
+    pub rewrite_extensions: Option<Vec<String>>,
+    /// Emit notes about the changes made to modules, for debugging.
+    #[serde(default)]
+    pub verbose: bool,
+    /// Regular expressions matched against every import source. Matching
+    /// sources are disallowed in both compilations.
+    #[serde(default)]
+    pub banned_source_patterns: Option<Vec<String>>,
+    /// Define `__esModule` on the `module.exports` of the CommonJS client entry
+    /// proxy, for interop layers that expect it. Doesn't apply to the `esm`
+    /// and `lazy` forms.
+    #[serde(default)]
+    pub mark_esmodule: bool,
+    /// Pass `{ id: "/path/to/file.js", name: "*" }` to `createProxy` instead of
+    /// the bare file path, for runtimes that expect a reference object.
+    #[serde(default)]
+    pub proxy_arg_object: bool,
+    /// Directives that mark client entries, on top of `"use client"`.
+    #[serde(default)]
+    pub client_directives: Option<Vec<String>>,
+    /// Directives that mark Server Actions modules, on top of `"use server"`.
+    #[serde(default)]
+    pub server_directives: Option<Vec<String>>,
+    /// Stop checking the module after the first error, instead of reporting
+    /// all of them.
+    #[serde(default)]
+    pub fail_fast: bool,
+    /// The curated ban lists to start from. Defaults to `nextAppRouter`.
+    #[serde(default)]
+    pub preset: Option<Preset>,
+    /// Sources disallowed in the Server Components compilation. Replaces the
+    /// ones of the preset.
+    #[serde(default)]
+    pub invalid_server_imports: Option<Vec<String>>,
+    /// Sources disallowed in the Client Components compilation. Replaces the
+    /// ones of the preset.
+    #[serde(default)]
+    pub invalid_client_imports: Option<Vec<String>>,
+    /// React APIs disallowed in the Server Components compilation. Replaces
+    /// the ones of the preset.
+    #[serde(default)]
+    pub invalid_server_react_apis: Option<Vec<String>>,
+    /// ReactDOM APIs disallowed in the Server Components compilation.
+    /// Replaces the ones of the preset.
+    #[serde(default)]
+    pub invalid_server_react_dom_apis: Option<Vec<String>>,
+    /// Warn about client entries importing one of `client_entry_sources`,
+    /// which is inside of the client boundary already.
+    #[serde(default)]
+    pub warn_nested_client_boundary: bool,
+}
+
+/// Curated sets of the sources and APIs disallowed in each compilation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Preset {
+    /// The bans of the Next.js App Router.
+    NextAppRouter,
+    /// Only `client-only` and `server-only`, without any React API bans.
+    Minimal,
+}
+
+impl Preset {
+    fn invalid_server_imports(self) -> &'static [&'static str] {
+        match self {
+            Preset::NextAppRouter => &["client-only", "react-dom/client", "react-dom/server"],
+            Preset::Minimal => &["client-only"],
+        }
+    }
+
+    fn invalid_client_imports(self) -> &'static [&'static str] {
+        &["server-only"]
+    }
+
+    fn invalid_server_react_apis(self) -> &'static [&'static str] {
+        match self {
+            Preset::NextAppRouter => &[
+                "Component",
+                "createContext",
+                "createFactory",
+                "PureComponent",
+                "useDeferredValue",
+                "useEffect",
+                "useImperativeHandle",
+                "useInsertionEffect",
+                "useLayoutEffect",
+                "useReducer",
+                "useRef",
+                "useState",
+                "useSyncExternalStore",
+                "useTransition",
+            ],
+            Preset::Minimal => &[],
+        }
+    }
+
+    fn invalid_server_react_dom_apis(self) -> &'static [&'static str] {
+        match self {
+            Preset::NextAppRouter => &["findDOMNode", "flushSync", "unstable_batchedUpdates"],
+            Preset::Minimal => &[],
+        }
+    }
+}
+
+// The configured list if there's one, the one of the preset otherwise.
+fn ban_list(configured: Option<Vec<String>>, preset: &[&str]) -> Vec<JsWord> {
+    match configured {
+        Some(list) => list.into_iter().map(JsWord::from).collect(),
+        None => preset.iter().copied().map(JsWord::from).collect(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    Nodejs,
+    Edge,
+}
+
+/// What the transform found and changed in a module, for tooling built on top
+/// of it. Collected through `server_components_with_analysis`.
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    /// Set in both compilations, so that the Client Components compilation
+    /// can tell client entries apart too. `None` if the module was skipped.
+    pub classification: Option<Classification>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub changes: Vec<Change>,
+    /// Imports of `client_entry_sources` by a server module.
+    pub client_entry_imports: Vec<(JsWord, Span)>,
+    /// React Hooks imported by the module, if `collect_hook_usage` is enabled.
+    pub hooks: Vec<JsWord>,
+    /// Whether the module is a server module that passed all of the checks of
+    /// the server graph without any diagnostic and doesn't import client
+    /// entries. Always `false` in the Client Components compilation.
+    pub pure: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Classification {
+    /// A module without a client directive.
+    Module,
+    /// A module marked with the client directive.
+    ClientEntry,
+    /// A module marked with the `"use server"` directive.
+    ServerActionEntry,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectiveKind {
+    /// `"use client"`, or `"client"`, the original spelling of the directive
+    /// that is still accepted.
+    Client,
+    /// `"use server"`.
+    Server,
+}
+
+impl DirectiveKind {
+    /// The spelling of the directive that new code should use.
+    pub fn canonical(self) -> &'static str {
+        match self {
+            DirectiveKind::Client => "use client",
+            DirectiveKind::Server => "use server",
+        }
+    }
+}
+
+/// Returns the kind of the directive `s`, or `None` if `s` isn't a directive
+/// handled by the transform. Only knows about the standard directives, not
+/// about `client_directives` and `server_directives`. This is the single place
+/// deciding which spellings mark client entries, both `"use client"` and the
+/// original `"client"` do.
+pub fn directive_kind(s: &str) -> Option<DirectiveKind> {
+    match s {
+        "use client" | "client" => Some(DirectiveKind::Client),
+        "use server" => Some(DirectiveKind::Server),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    /// Information for debugging the transform, only emitted in `verbose`
+    /// mode.
+    Note,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub span: Span,
+    /// Byte offsets of the start and end of `span` into the source of the
+    /// file, for tools that can't use the span itself. They're relative to
+    /// the `file_start_pos` the transform was given.
+    /// Diagnostics without a position have an empty range at 0.
+    pub start: u32,
+    pub end: u32,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A directive was removed from the top of the module.
+    DirectiveRemoved { directive: JsWord, span: Span },
+    /// The body of a client entry was replaced by the module reference. The
+    /// old length doesn't include the removed directives.
+    BodyReplaced { old_len: usize, new_len: usize },
 }
 
 struct ReactServerComponents<C: Comments> {
     is_server: bool,
+    // Check each module against the compilation it belongs to (see
+    // `check_module`) instead of the one `is_server` selects.
+    check_both_graphs: bool,
+    is_excluded: bool,
     filepath: String,
     comments: C,
+    analysis: Rc<RefCell<Analysis>>,
     invalid_server_imports: Vec<JsWord>,
     invalid_client_imports: Vec<JsWord>,
+    invalid_action_imports: Vec<JsWord>,
+    internal_packages: Vec<JsWord>,
+    banned_source_patterns: Vec<Regex>,
+    discouraged_client_imports: Vec<JsWord>,
+    client_entry_sources: Vec<JsWord>,
+    server_entry_sources: Vec<JsWord>,
     invalid_server_react_apis: Vec<JsWord>,
     invalid_server_react_dom_apis: Vec<JsWord>,
+    react_aliases: Vec<JsWord>,
+    lazy: bool,
+    esm: bool,
+    alias_map: AHashMap<JsWord, JsWord>,
+    allowed_server_specifiers: AHashMap<JsWord, Vec<JsWord>>,
+    runtime: Runtime,
+    proxy_module: Option<String>,
+    edge_proxy_module: Option<String>,
+    proxy_prelude: Vec<Stmt>,
+    marker_as_export: bool,
+    mark_esmodule: bool,
+    proxy_arg_object: bool,
+    client_directives: Vec<String>,
+    server_directives: Vec<String>,
+    warn_non_component_client_default: bool,
+    // Whether the extension of the file is one of `rewrite_extensions`.
+    is_rewritten_extension: bool,
+    verbose: bool,
+    ban_node_builtins_on_client: bool,
+    // Local bindings of disallowed React and ReactDOM APIs imported by a
+    // server module, mapped to the imported API name and its package label.
+    disallowed_api_bindings: AHashMap<Id, (JsWord, &'static str)>,
+    // The exports calling each of `disallowed_api_bindings` through the
+    // imported name, for the diagnostics of the imports.
+    disallowed_api_exports: AHashMap<Id, Vec<JsWord>>,
+    check_top_level_hooks: bool,
+    collect_hook_usage: bool,
+    strict_directive_prologue: bool,
+    lenient_directive_position: bool,
+    severity: Severity,
+    source_severity: AHashMap<JsWord, Severity>,
+    // Local bindings of the React Hooks imported by a client module, mapped to
+    // the imported Hook name.
+    hook_bindings: AHashMap<Id, JsWord>,
+    // How many functions deep the visitor currently is, 0 being the top level
+    // of the module.
+    function_depth: usize,
+    // Name of the export declaration currently being visited, if any. Only
+    // exports declared in place are tracked, not `export { name }`.
+    current_export: Option<JsWord>,
+    // Whether the transform was configured through `Config::All`.
+    is_config_all: bool,
+    fail_fast: bool,
+    warn_nested_client_boundary: bool,
+    file_start_pos: BytePos,
+}
+
+/// How a module is imported, which determines how diagnostics refer to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportKind {
+    SideEffect,
+    Default,
+    Named,
+    Namespace,
+    ReExport,
+}
+
+impl ImportKind {
+    fn from_specifiers(specifiers: &[ImportSpecifier]) -> Self {
+        if specifiers
+            .iter()
+            .any(|s| matches!(s, ImportSpecifier::Namespace(_)))
+        {
+            ImportKind::Namespace
+        } else if specifiers
+            .iter()
+            .any(|s| matches!(s, ImportSpecifier::Named(_)))
+        {
+            ImportKind::Named
+        } else if specifiers.is_empty() {
+            ImportKind::SideEffect
+        } else {
+            ImportKind::Default
+        }
+    }
+
+    // How the import is referred to in diagnostics.
+    fn describe(&self) -> &'static str {
+        match self {
+            ImportKind::SideEffect => "import",
+            ImportKind::Default => "default import",
+            ImportKind::Named => "named import",
+            ImportKind::Namespace => "namespace import",
+            ImportKind::ReExport => "re-export",
+        }
+    }
 }
 
-struct ModuleImports {
-    source: (JsWord, Span),
-    specifiers: Vec<(JsWord, Span)>,
+/// An import or re-export of a module, as seen by the checks.
+#[derive(Clone, Debug)]
+pub struct ModuleImports {
+    /// The source after resolving `alias_map`, and the span of the import.
+    pub source: (JsWord, Span),
+    pub kind: ImportKind,
+    /// Imported name, span and local binding of each specifier.
+    pub specifiers: Vec<(JsWord, Span, Id)>,
 }
 
 impl<C: Comments> VisitMut for ReactServerComponents<C> {
     noop_visit_mut_type!();
 
     fn visit_mut_module(&mut self, module: &mut Module) {
-        let (is_client_entry, imports) = self.collect_top_level_directives_and_imports(module);
+        if self.is_config_all && !CONFIG_ALL_DEPRECATION_REPORTED.swap(true, Ordering::Relaxed) {
+            self.report(
+                Severity::Warning,
+                DUMMY_SP,
+                "Configuring the Server Components transform with a boolean is deprecated, pass \
+                 the options instead, e.g. `{ isServer: true }`."
+                    .into(),
+            );
+        }
+
+        if self.is_excluded || self.is_ignored_file(module) {
+            return;
+        }
+
+        let (classification, imports) = self.collect_top_level_directives_and_imports(module);
+        let is_client_entry = classification == Classification::ClientEntry;
+        let is_action_entry = classification == Classification::ServerActionEntry;
+
+        self.analysis.borrow_mut().classification = Some(classification);
+
+        if self.check_both_graphs {
+            // Client entries belong to the client graph and are therefore not
+            // rewritten, everything else belongs to the server graph.
+            self.is_server = !is_client_entry;
+        }
+
+        if self.is_server && !is_client_entry {
+            self.record_client_entry_imports(&imports);
+        }
+
+        if !self.is_server && self.check_top_level_hooks {
+            self.hook_bindings = self.collect_hook_bindings(&imports);
+        }
+
+        if self.collect_hook_usage {
+            self.record_hook_usage(&imports);
+        }
+
+        let is_server_module = self.is_server && !is_client_entry;
+        let checked_from = self.analysis.borrow().diagnostics.len();
 
         if self.is_server {
-            if !is_client_entry {
+            // Client entries that aren't rewritten keep their code, which then
+            // has to pass the checks of the server graph.
+            if !is_client_entry || !self.is_rewritten_extension {
+                self.disallowed_api_bindings = self.collect_disallowed_api_bindings(&imports);
+                if !self.disallowed_api_bindings.is_empty() {
+                    let mut uses = ExportUses {
+                        bindings: &self.disallowed_api_bindings,
+                        current_export: None,
+                        exports: Default::default(),
+                    };
+                    module.visit_with(&mut uses);
+                    self.disallowed_api_exports = uses.exports;
+                }
                 self.assert_server_graph(&imports);
+                if is_action_entry {
+                    self.assert_action_graph(&imports);
+                }
             } else {
+                if self.warn_non_component_client_default {
+                    self.assert_component_default_export(module);
+                }
                 self.to_module_ref(module);
                 return;
             }
         } else {
+            if is_client_entry && self.warn_nested_client_boundary {
+                self.assert_no_nested_client_boundary(&imports);
+            }
             self.assert_client_graph(&imports);
         }
-        module.visit_mut_children_with(self)
+        if self.has_failed() {
+            return;
+        }
+        module.visit_mut_children_with(self);
+        if is_server_module {
+            self.record_purity(checked_from);
+        }
+    }
+
+    fn visit_mut_call_expr(&mut self, expr: &mut CallExpr) {
+        if let Callee::Expr(callee) = &expr.callee {
+            if let Expr::Ident(ident) = &**callee {
+                // Calls through the imported name are obvious from the diagnostic of
+                // the import already, only the aliased ones are reported.
+                if let Some((api, label)) = self
+                    .disallowed_api_bindings
+                    .get(&ident.to_id())
+                    .filter(|(api, _)| ident.sym != *api)
+                {
+                    let message = format!(
+                        "Disallowed {} API `{}` (imported as `{}`) called in the Server \
+                         Components compilation.{}",
+                        label,
+                        api,
+                        ident.sym,
+                        used_in_exports(
+                            self.current_export
+                                .as_ref()
+                                .map(std::slice::from_ref)
+                                .unwrap_or_default()
+                        )
+                    );
+                    self.report(Severity::Error, expr.span, message);
+                }
+
+                if self.function_depth == 0 {
+                    if let Some(hook) = self.hook_bindings.get(&ident.to_id()) {
+                        self.report(
+                            Severity::Error,
+                            expr.span,
+                            format!(
+                                "React Hook `{}` can't be called at the top level of a module. \
+                                 Hooks can only be called inside of a component or another Hook.",
+                                hook
+                            ),
+                        )
+                    }
+                }
+            }
+        }
+
+        expr.visit_mut_children_with(self)
+    }
+
+    fn visit_mut_export_decl(&mut self, export: &mut ExportDecl) {
+        match &mut export.decl {
+            Decl::Var(var) => {
+                for decl in &mut var.decls {
+                    self.visit_mut_in_export(var_export_name(decl), decl);
+                }
+            }
+            decl => self.visit_mut_in_export(decl_export_name(decl), decl),
+        }
+    }
+
+    fn visit_mut_export_default_decl(&mut self, export: &mut ExportDefaultDecl) {
+        self.visit_mut_in_export(Some(default_export_name(&export.decl)), export);
+    }
+
+    fn visit_mut_export_default_expr(&mut self, export: &mut ExportDefaultExpr) {
+        self.visit_mut_in_export(Some(js_word!("default")), export);
+    }
+
+    fn visit_mut_function(&mut self, function: &mut Function) {
+        self.function_depth += 1;
+        function.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_mut_arrow_expr(&mut self, arrow: &mut ArrowExpr) {
+        self.function_depth += 1;
+        arrow.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_mut_constructor(&mut self, constructor: &mut Constructor) {
+        self.function_depth += 1;
+        constructor.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_mut_getter_prop(&mut self, getter: &mut GetterProp) {
+        self.function_depth += 1;
+        getter.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    fn visit_mut_setter_prop(&mut self, setter: &mut SetterProp) {
+        self.function_depth += 1;
+        setter.visit_mut_children_with(self);
+        self.function_depth -= 1;
+    }
+
+    // Class field initializers run when the class is instantiated, not at the
+    // top level. Static ones run along with the class declaration.
+    fn visit_mut_class_prop(&mut self, prop: &mut ClassProp) {
+        let depth = usize::from(!prop.is_static);
+        self.function_depth += depth;
+        prop.visit_mut_children_with(self);
+        self.function_depth -= depth;
+    }
+
+    fn visit_mut_private_prop(&mut self, prop: &mut PrivateProp) {
+        let depth = usize::from(!prop.is_static);
+        self.function_depth += depth;
+        prop.visit_mut_children_with(self);
+        self.function_depth -= depth;
+    }
+
+    fn visit_mut_ts_module_block(&mut self, block: &mut TsModuleBlock) {
+        // Directives are only meaningful at the top of the file, warn about the
+        // ones written inside of a namespace or module block instead.
+        for item in &block.body {
+            if let ModuleItem::Stmt(Stmt::Expr(ExprStmt { expr, .. })) = item {
+                if let Expr::Lit(Lit::Str(Str { value, span, .. })) = &**expr {
+                    if self.directive_kind(value).is_some() {
+                        self.report(
+                            Severity::Warning,
+                            *span,
+                            format!(
+                                "The `\"{}\"` directive has no effect inside a TypeScript \
+                                 namespace or module block. Move it to the top of the file.",
+                                value
+                            ),
+                        )
+                    }
+                }
+            }
+        }
+
+        block.visit_mut_children_with(self)
     }
 }
 
 impl<C: Comments> ReactServerComponents<C> {
+    // Emits a diagnostic and records it in the analysis.
+    fn report(&self, severity: Severity, span: Span, message: String) {
+        if self.has_failed() {
+            return;
+        }
+        // There's no handler when the checks are run on their own through
+        // `check_imports`.
+        if HANDLER.is_set() {
+            HANDLER.with(|handler| match severity {
+                Severity::Error => handler.struct_span_err(span, &message).emit(),
+                Severity::Warning => handler.struct_span_warn(span, &message).emit(),
+                Severity::Note => handler.span_note_without_error(span, &message),
+            });
+        }
+        self.analysis.borrow_mut().diagnostics.push(Diagnostic {
+            severity,
+            span,
+            start: span.lo.0.saturating_sub(self.file_start_pos.0),
+            end: span.hi.0.saturating_sub(self.file_start_pos.0),
+            message,
+        });
+    }
+
+    // Whether checking has to stop because of `fail_fast`. Only errors count,
+    // so that a warning can't hide an error.
+    fn has_failed(&self) -> bool {
+        self.fail_fast
+            && self
+                .analysis
+                .borrow()
+                .diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    // Like `directive_kind`, but also recognizes the configured directives.
+    fn directive_kind(&self, s: &str) -> Option<DirectiveKind> {
+        directive_kind(s).or_else(|| {
+            if self
+                .client_directives
+                .iter()
+                .any(|directive| directive == s)
+            {
+                Some(DirectiveKind::Client)
+            } else if self
+                .server_directives
+                .iter()
+                .any(|directive| directive == s)
+            {
+                Some(DirectiveKind::Server)
+            } else {
+                None
+            }
+        })
+    }
+
+    // Whether the module opts out of the transform with a leading
+    // `// @next-rsc-ignore-file` comment.
+    fn is_ignored_file(&self, module: &Module) -> bool {
+        self.comments.with_leading(module.span.lo, |comments| {
+            comments
+                .iter()
+                .any(|comment| comment.text.trim() == "@next-rsc-ignore-file")
+        })
+    }
+
+    // Aliases are resolved first so that all of the checks see the actual
+    // package.
+    fn resolve_source(&self, source: &JsWord) -> JsWord {
+        self.alias_map
+            .get(source)
+            .cloned()
+            .unwrap_or_else(|| source.clone())
+    }
+
     // Collects top level directives and imports, then removes specific ones
     // from the AST.
     fn collect_top_level_directives_and_imports(
         &self,
         module: &mut Module,
-    ) -> (bool, Vec<ModuleImports>) {
+    ) -> (Classification, Vec<ModuleImports>) {
         let mut imports: Vec<ModuleImports> = vec![];
         let mut finished_directives = false;
         let mut is_client_entry = false;
+        let mut is_action_entry = false;
 
-        let _ = &module.body.retain(|item| {
+        // Client entries that aren't rewritten keep their directive, so that
+        // downstream tooling can still tell where the client boundary is.
+        let keep_client_directives =
+            self.is_server && !self.check_both_graphs && !self.is_rewritten_extension;
+        let module_start = module.span.lo;
+        let mut keep_item = |item: &ModuleItem| {
             match item {
                 ModuleItem::Stmt(stmt) => {
                     if !finished_directives {
@@ -92,13 +851,68 @@ impl<C: Comments> ReactServerComponents<C> {
                         match stmt.as_expr() {
                             Some(expr_stmt) => {
                                 match &*expr_stmt.expr {
-                                    Expr::Lit(Lit::Str(Str { value, .. })) => {
-                                        if &**value == "client" {
+                                    Expr::Lit(Lit::Str(Str { value, span, .. })) => {
+                                        if self.directive_kind(value) == Some(DirectiveKind::Client)
+                                        {
+                                            if is_client_entry {
+                                                self.report(
+                                                    Severity::Warning,
+                                                    *span,
+                                                    format!(
+                                                        "Duplicate `\"{}\"` directive, the module \
+                                                         is already a client entry.",
+                                                        value
+                                                    ),
+                                                );
+                                            }
                                             is_client_entry = true;
+                                            if keep_client_directives {
+                                                return true;
+                                            }
+                                            self.analysis.borrow_mut().changes.push(
+                                                Change::DirectiveRemoved {
+                                                    directive: value.clone(),
+                                                    span: expr_stmt.span,
+                                                },
+                                            );
 
                                             // Remove the directive.
                                             return false;
                                         }
+
+                                        // Any casing of a directive suggests its
+                                        // canonical spelling, e.g. `"Client"`
+                                        // suggests `"use client"`.
+                                        if let (None, Some(kind)) = (
+                                            self.directive_kind(value),
+                                            self.directive_kind(&value.to_lowercase()),
+                                        ) {
+                                            self.report(
+                                                Severity::Warning,
+                                                *span,
+                                                format!(
+                                                    "`\"{}\"` is not a valid directive, did you \
+                                                     mean `\"{}\"`?",
+                                                    value,
+                                                    kind.canonical()
+                                                ),
+                                            );
+                                        }
+
+                                        if self.directive_kind(value) == Some(DirectiveKind::Server)
+                                        {
+                                            // Server Actions modules keep their
+                                            // directive.
+                                            is_action_entry = true;
+                                            return true;
+                                        }
+
+                                        if self.strict_directive_prologue
+                                            && &**value != "use strict"
+                                        {
+                                            // Unknown strings end the prologue.
+                                            finished_directives = true;
+                                        }
                                     }
                                     _ => {
                                         // Other expression types.
@@ -111,190 +925,1377 @@ impl<C: Comments> ReactServerComponents<C> {
                                 finished_directives = true;
                             }
                         }
+                    } else if let Some(ExprStmt { expr, span }) = stmt.as_expr() {
+                        // The prologue ended already, e.g. with an import.
+                        if let Expr::Lit(Lit::Str(value)) = &**expr {
+                            match self.directive_kind(&value.value) {
+                                Some(DirectiveKind::Client) if self.lenient_directive_position => {
+                                    is_client_entry = true;
+                                    if keep_client_directives {
+                                        return true;
+                                    }
+                                    self.analysis.borrow_mut().changes.push(
+                                        Change::DirectiveRemoved {
+                                            directive: value.value.clone(),
+                                            span: *span,
+                                        },
+                                    );
+                                    return false;
+                                }
+                                // `"client"` is too common of a string to warn
+                                // about outside of the prologue.
+                                Some(_) if value.value.starts_with("use ") => self.report(
+                                    Severity::Warning,
+                                    value.span,
+                                    format!(
+                                        "The `\"{}\"` directive is ignored because it isn't at \
+                                         the top of the module. Directives must come before any \
+                                         imports or other statements.",
+                                        value.value
+                                    ),
+                                ),
+                                _ => {}
+                            }
+                        }
                     }
                 }
                 ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
-                    let source = import.src.value.clone();
+                    // An empty source can't refer to any package (it only shows
+                    // up in generated code), so it must never match a ban list
+                    // entry.
+                    if import.src.value.is_empty() {
+                        finished_directives = true;
+                        return true;
+                    }
+
+                    let source = self.resolve_source(&import.src.value);
                     let specifiers = import
                         .specifiers
                         .iter()
                         .map(|specifier| match specifier {
                             ImportSpecifier::Named(named) => match &named.imported {
                                 Some(imported) => match &imported {
-                                    ModuleExportName::Ident(i) => (i.to_id().0, i.span),
-                                    ModuleExportName::Str(s) => (s.value.clone(), s.span),
+                                    ModuleExportName::Ident(i) => {
+                                        (i.to_id().0, i.span, named.local.to_id())
+                                    }
+                                    ModuleExportName::Str(s) => {
+                                        (s.value.clone(), s.span, named.local.to_id())
+                                    }
                                 },
-                                None => (named.local.to_id().0, named.local.span),
+                                None => {
+                                    (named.local.to_id().0, named.local.span, named.local.to_id())
+                                }
                             },
-                            ImportSpecifier::Default(d) => (js_word!(""), d.span),
-                            ImportSpecifier::Namespace(n) => ("*".into(), n.span),
+                            ImportSpecifier::Default(d) => (js_word!(""), d.span, d.local.to_id()),
+                            ImportSpecifier::Namespace(n) => ("*".into(), n.span, n.local.to_id()),
                         })
                         .collect();
 
                     imports.push(ModuleImports {
                         source: (source, import.span),
+                        kind: ImportKind::from_specifiers(&import.specifiers),
                         specifiers,
                     });
 
                     finished_directives = true;
                 }
+                // Re-exports pull in their source just like imports do. They
+                // don't create local bindings, so there are no specifiers to
+                // track.
+                ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(NamedExport {
+                    span,
+                    src: Some(src),
+                    ..
+                })) => {
+                    if !src.value.is_empty() {
+                        imports.push(ModuleImports {
+                            source: (self.resolve_source(&src.value), *span),
+                            kind: ImportKind::ReExport,
+                            specifiers: vec![],
+                        });
+                    }
+                    finished_directives = true;
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportAll(export)) => {
+                    if !export.src.value.is_empty() {
+                        imports.push(ModuleImports {
+                            source: (self.resolve_source(&export.src.value), export.span),
+                            kind: ImportKind::ReExport,
+                            specifiers: vec![],
+                        });
+                    }
+                    finished_directives = true;
+                }
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export))
+                    if !finished_directives =>
+                {
+                    if let Expr::Lit(Lit::Str(Str { value, span, .. })) = &*export.expr {
+                        if self.directive_kind(value).is_some() {
+                            self.report(
+                                Severity::Warning,
+                                *span,
+                                format!(
+                                    "`\"{}\"` is exported instead of being a directive, \
+                                     directives can't be exported. Move it to its own statement \
+                                     at the top of the module.",
+                                    value
+                                ),
+                            );
+                        }
+                    }
+                    finished_directives = true;
+                }
                 _ => {
                     finished_directives = true;
                 }
             }
             true
+        };
+
+        // The comments of removed directives would be lost with them, so they
+        // are moved to the next statement that is kept. Comments at the start
+        // of the module are printed with the module itself and can stay.
+        let mut orphaned_comments = vec![];
+        module.body.retain(|item| {
+            let keep = keep_item(item);
+            let pos = item.span().lo;
+            if !keep {
+                if pos != module_start {
+                    if let Some(comments) = self.comments.take_leading(pos) {
+                        orphaned_comments.extend(comments);
+                    }
+                }
+            } else if !orphaned_comments.is_empty() {
+                let mut comments = std::mem::take(&mut orphaned_comments);
+                comments.extend(self.comments.take_leading(pos).unwrap_or_default());
+                self.comments.add_leading_comments(pos, comments);
+            }
+            keep
         });
 
-        (is_client_entry, imports)
+        let classification = if is_client_entry {
+            Classification::ClientEntry
+        } else if is_action_entry {
+            Classification::ServerActionEntry
+        } else {
+            Classification::Module
+        };
+        (classification, imports)
     }
 
     // Convert the client module to the module reference code and add a special
     // comment to the top of the file.
     fn to_module_ref(&self, module: &mut Module) {
+        let old_len = module.body.len();
+        // Give the generated code the span of the original module so that
+        // source maps can at least point to the file. Synthetic modules can
+        // have a dummy span, use the first statement with a real one instead.
+        let span = if module.span.is_dummy() {
+            module
+                .body
+                .iter()
+                .map(|item| item.span())
+                .find(|span| !span.is_dummy())
+                .unwrap_or(DUMMY_SP)
+        } else {
+            module.span
+        };
+
+        // The `esm` form re-exports the named exports, they're declared by the
+        // generated code too.
+        let export_names = if self.esm {
+            named_export_names(module)
+        } else {
+            vec![]
+        };
+
         // Clear all the statements and module declarations.
         module.body.clear();
 
-        let proxy_ident = quote_ident!("createProxy");
+        let proxy_ident = self.free_ident("createProxy", &export_names);
         let filepath = quote_str!(&*self.filepath);
 
-        prepend_stmts(
-            &mut module.body,
-            vec![
-                ModuleItem::Stmt(Stmt::Decl(Decl::Var(VarDecl {
+        let require_proxy = Stmt::Decl(Decl::Var(VarDecl {
+            span,
+            kind: VarDeclKind::Const,
+            decls: vec![VarDeclarator {
+                span,
+                name: Pat::Object(ObjectPat {
                     span: DUMMY_SP,
-                    kind: VarDeclKind::Const,
-                    decls: vec![VarDeclarator {
-                        span: DUMMY_SP,
-                        name: Pat::Object(ObjectPat {
+                    props: vec![if &*proxy_ident.sym == "createProxy" {
+                        ObjectPatProp::Assign(AssignPatProp {
                             span: DUMMY_SP,
-                            props: vec![ObjectPatProp::Assign(AssignPatProp {
-                                span: DUMMY_SP,
-                                key: proxy_ident,
-                                value: None,
-                            })],
-                            optional: false,
-                            type_ann: None,
-                        }),
-                        init: Some(Box::new(Expr::Call(CallExpr {
-                            span: DUMMY_SP,
-                            callee: quote_ident!("require").as_callee(),
-                            args: vec![quote_str!("private-next-rsc-mod-ref-proxy").as_arg()],
-                            type_args: Default::default(),
-                        }))),
-                        definite: false,
+                            key: proxy_ident.clone(),
+                            value: None,
+                        })
+                    } else {
+                        ObjectPatProp::KeyValue(KeyValuePatProp {
+                            key: PropName::Ident(quote_ident!("createProxy")),
+                            value: Box::new(Pat::Ident(proxy_ident.clone().into())),
+                        })
                     }],
-                    declare: false,
+                    optional: false,
+                    type_ann: None,
+                }),
+                init: Some(Box::new(Expr::Call(CallExpr {
+                    span: DUMMY_SP,
+                    callee: quote_ident!("require").as_callee(),
+                    args: vec![quote_str!(self.proxy_module()).as_arg()],
+                    type_args: Default::default(),
                 }))),
-                ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                definite: false,
+            }],
+            declare: false,
+        }));
+        let proxy_arg = if self.proxy_arg_object {
+            Expr::Object(ObjectLit {
+                span: DUMMY_SP,
+                props: vec![
+                    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                        key: PropName::Ident(quote_ident!("id")),
+                        value: Box::new(Expr::Lit(Lit::Str(filepath))),
+                    }))),
+                    PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                        key: PropName::Ident(quote_ident!("name")),
+                        value: Box::new(Expr::Lit(Lit::Str(quote_str!("*")))),
+                    }))),
+                ],
+            })
+        } else {
+            Expr::Lit(Lit::Str(filepath))
+        };
+        let create_proxy = Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: proxy_ident.clone().as_callee(),
+            args: vec![proxy_arg.as_arg()],
+            type_args: Default::default(),
+        });
+
+        let body = if self.esm {
+            let mut body = vec![ModuleItem::ModuleDecl(ModuleDecl::Import(ImportDecl {
+                span,
+                specifiers: vec![ImportSpecifier::Named(ImportNamedSpecifier {
                     span: DUMMY_SP,
-                    expr: Box::new(Expr::Assign(AssignExpr {
+                    imported: if &*proxy_ident.sym == "createProxy" {
+                        None
+                    } else {
+                        Some(ModuleExportName::Ident(quote_ident!("createProxy")))
+                    },
+                    local: proxy_ident,
+                    is_type_only: false,
+                })],
+                src: quote_str!(self.proxy_module()).into(),
+                type_only: false,
+                asserts: None,
+            }))];
+            let proxy = self.free_ident("proxy", &export_names);
+            body.push(ModuleItem::Stmt(Stmt::Decl(Decl::Var(VarDecl {
+                span,
+                kind: VarDeclKind::Const,
+                decls: vec![VarDeclarator {
+                    span,
+                    name: Pat::Ident(proxy.clone().into()),
+                    init: Some(Box::new(create_proxy)),
+                    definite: false,
+                }],
+                declare: false,
+            }))));
+            body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(
+                ExportDefaultExpr {
+                    span,
+                    expr: Box::new(Expr::Ident(proxy.clone())),
+                },
+            )));
+            // export const Button = proxy.Button;
+            for name in export_names {
+                body.push(ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                    span: DUMMY_SP,
+                    decl: Decl::Var(VarDecl {
                         span: DUMMY_SP,
+                        kind: VarDeclKind::Const,
+                        decls: vec![VarDeclarator {
+                            span: DUMMY_SP,
+                            name: Pat::Ident(quote_ident!(name.clone()).into()),
+                            init: Some(Box::new(Expr::Member(MemberExpr {
+                                span: DUMMY_SP,
+                                obj: Box::new(Expr::Ident(proxy.clone())),
+                                prop: MemberProp::Ident(quote_ident!(name)),
+                            }))),
+                            definite: false,
+                        }],
+                        declare: false,
+                    }),
+                })));
+            }
+            body
+        } else if self.lazy {
+            lazy_module_ref(
+                span,
+                self.free_ident("proxy", &export_names),
+                require_proxy,
+                create_proxy,
+            )
+        } else {
+            let mut body = vec![
+                ModuleItem::Stmt(require_proxy),
+                ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+                    span,
+                    expr: Box::new(Expr::Assign(AssignExpr {
+                        span,
                         left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr {
                             span: DUMMY_SP,
                             obj: Box::new(Expr::Ident(quote_ident!("module"))),
                             prop: MemberProp::Ident(quote_ident!("exports")),
                         }))),
                         op: op!("="),
-                        right: Box::new(Expr::Call(CallExpr {
-                            span: DUMMY_SP,
-                            callee: quote_ident!("createProxy").as_callee(),
-                            args: vec![filepath.as_arg()],
-                            type_args: Default::default(),
-                        })),
+                        right: Box::new(create_proxy),
                     })),
                 })),
-            ]
-            .into_iter(),
-        );
+            ];
+            if self.mark_esmodule {
+                body.push(esmodule_flag(span));
+            }
+            body
+        };
 
-        // Prepend a special comment to the top of the file.
-        self.comments.add_leading(
-            module.span.lo,
-            Comment {
-                span: DUMMY_SP,
-                kind: CommentKind::Block,
-                text: " __next_internal_client_entry_do_not_use__ ".into(),
-            },
-        );
+        let mut prelude = self.proxy_prelude.clone();
+        // The comments at the start of the module, including the marker added
+        // below, are attached to `span`. The first statement of the prelude
+        // gets it too so that they stay at the top.
+        if let Some(first) = prelude.first_mut() {
+            if !span.is_dummy() {
+                first.visit_mut_with(&mut Respan(span));
+            }
+        }
+        let prelude = prelude.into_iter().map(ModuleItem::Stmt);
+        prepend_stmts(&mut module.body, prelude.chain(body));
+
+        // Only the ES module proxy can carry the marker, the CommonJS forms are
+        // rejected when the transform is created.
+        if self.marker_as_export && self.esm {
+            module
+                .body
+                .push(ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(ExportDecl {
+                    span: DUMMY_SP,
+                    decl: Decl::Var(VarDecl {
+                        span: DUMMY_SP,
+                        kind: VarDeclKind::Const,
+                        decls: vec![VarDeclarator {
+                            span: DUMMY_SP,
+                            name: Pat::Ident(quote_ident!("__next_rsc_client_entry__").into()),
+                            init: Some(Box::new(Expr::Lit(Lit::Bool(Bool {
+                                span: DUMMY_SP,
+                                value: true,
+                            })))),
+                            definite: false,
+                        }],
+                        declare: false,
+                    }),
+                })));
+        }
+
+        self.analysis
+            .borrow_mut()
+            .changes
+            .push(Change::BodyReplaced {
+                old_len,
+                new_len: module.body.len(),
+            });
+        if self.verbose {
+            self.report(
+                Severity::Note,
+                span,
+                format!(
+                    "Replaced the {} top level statements and declarations of the client entry \
+                     with the module reference.",
+                    old_len
+                ),
+            );
+        }
+
+        // Prepend a special comment to the top of the file. Without any real
+        // position it would be attached to position 0 and collide with
+        // unrelated comments, so it's skipped.
+        if !span.is_dummy() {
+            self.comments.add_leading(
+                span.lo,
+                Comment {
+                    span: DUMMY_SP,
+                    kind: CommentKind::Block,
+                    text: " __next_internal_client_entry_do_not_use__ ".into(),
+                },
+            );
+        }
     }
 
-    fn assert_server_graph(&self, imports: &Vec<ModuleImports>) {
+    // Maps the local bindings of disallowed React and ReactDOM APIs to the API
+    // they were imported as, so that aliased usages can be reported too.
+    fn collect_disallowed_api_bindings(
+        &self,
+        imports: &[ModuleImports],
+    ) -> AHashMap<Id, (JsWord, &'static str)> {
+        let mut bindings = AHashMap::default();
         for import in imports {
+            let source = &import.source.0;
+            let mut checks = vec![];
+            if self.is_react_source(source) {
+                checks.push((&self.invalid_server_react_apis, "React"));
+            }
+            if self.is_react_dom_source(source) {
+                checks.push((&self.invalid_server_react_dom_apis, "ReactDOM"));
+            }
+            for (apis, label) in checks {
+                for specifier in &import.specifiers {
+                    if self.is_disallowed_api(apis, source, &specifier.0) {
+                        bindings.insert(specifier.2.clone(), (specifier.0.clone(), label));
+                    }
+                }
+            }
+        }
+        bindings
+    }
+
+    // Whether `specifier`, imported from `source`, is one of the disallowed
+    // `apis`. Default imports are recorded with an empty name and never match,
+    // even if the list contains an empty entry.
+    fn is_disallowed_api(&self, apis: &[JsWord], source: &JsWord, specifier: &JsWord) -> bool {
+        !specifier.is_empty()
+            && apis.contains(specifier)
+            && !self.is_allowed_server_specifier(source, specifier)
+    }
+
+    // Whether `specifier` is explicitly allowed when imported from `source`.
+    fn is_allowed_server_specifier(&self, source: &JsWord, specifier: &JsWord) -> bool {
+        self.allowed_server_specifiers
+            .get(source)
+            .map_or(false, |allowed| allowed.contains(specifier))
+    }
+
+    // Maps the local bindings of the Hooks imported from `react` to their
+    // imported name.
+    fn collect_hook_bindings(&self, imports: &[ModuleImports]) -> AHashMap<Id, JsWord> {
+        imports
+            .iter()
+            .filter(|import| self.is_react_source(&import.source.0))
+            .flat_map(|import| import.specifiers.iter())
+            .filter(|specifier| specifier.0.starts_with("use"))
+            .map(|specifier| (specifier.2.clone(), specifier.0.clone()))
+            .collect()
+    }
+
+    // Whether `source` is `react` or one of the configured aliases of it.
+    fn is_react_source(&self, source: &JsWord) -> bool {
+        *source == *"react" || self.react_aliases.contains(source)
+    }
+
+    // Whether `source` is `react-dom` or one of the configured aliases of it.
+    fn is_react_dom_source(&self, source: &JsWord) -> bool {
+        *source == *"react-dom" || self.react_aliases.contains(source)
+    }
+
+    // Records the Hooks imported from `react`, in import order.
+    fn record_hook_usage(&self, imports: &[ModuleImports]) {
+        let mut analysis = self.analysis.borrow_mut();
+        for import in imports
+            .iter()
+            .filter(|import| self.is_react_source(&import.source.0))
+        {
+            for specifier in &import.specifiers {
+                if specifier.0.starts_with("use") && !analysis.hooks.contains(&specifier.0) {
+                    analysis.hooks.push(specifier.0.clone());
+                }
+            }
+        }
+    }
+
+    fn record_client_entry_imports(&self, imports: &[ModuleImports]) {
+        let mut analysis = self.analysis.borrow_mut();
+        for import in imports {
+            if self.client_entry_sources.contains(&import.source.0) {
+                analysis.client_entry_imports.push(import.source.clone());
+            }
+        }
+    }
+
+    // Records whether the server module passed the checks of the server graph,
+    // which reported the diagnostics after the first `checked_from` ones.
+    fn record_purity(&self, checked_from: usize) {
+        let mut analysis = self.analysis.borrow_mut();
+        analysis.pure =
+            analysis.diagnostics.len() == checked_from && analysis.client_entry_imports.is_empty();
+    }
+
+    // The sentence naming the exports that call the disallowed API bound to
+    // `binding`, if any.
+    fn used_in_exports_of(&self, binding: &Id) -> String {
+        used_in_exports(
+            self.disallowed_api_exports
+                .get(binding)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        )
+    }
+
+    // Visits the children of `node` with `name` as the export the visited code
+    // belongs to, so that diagnostics can point to it.
+    fn visit_mut_in_export<N: VisitMutWith<Self>>(&mut self, name: Option<JsWord>, node: &mut N) {
+        self.current_export = name;
+        node.visit_mut_children_with(self);
+        self.current_export = None;
+    }
+
+    // Returns the first of the banned source patterns that matches `source`.
+    fn banned_source_pattern(&self, source: &str) -> Option<&Regex> {
+        self.banned_source_patterns
+            .iter()
+            .find(|pattern| pattern.is_match(source))
+    }
+
+    // Reports the import if its source matches one of the banned patterns.
+    fn assert_banned_source_patterns(&self, import: &ModuleImports, compilation: &str) {
+        if let Some(pattern) = self.banned_source_pattern(&import.source.0) {
+            self.report(
+                self.severity_for(&import.source.0),
+                import.source.1,
+                format!(
+                    "Disallowed {} of `{}` in the {} compilation, it matches the banned pattern \
+                     `{}`.",
+                    import.kind.describe(),
+                    import.source.0,
+                    compilation,
+                    pattern
+                ),
+            )
+        }
+    }
+
+    // Whether `source` is one of the internal packages or a subpath of one.
+    fn is_internal_package(&self, source: &str) -> bool {
+        self.internal_packages.iter().any(|package| {
+            source
+                .strip_prefix(&**package)
+                .map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    // Whether `source` is a Node.js builtin, including subpaths like
+    // `fs/promises`, that is disallowed in the Client Components compilation.
+    fn is_banned_node_builtin(&self, source: &str) -> bool {
+        if !self.ban_node_builtins_on_client {
+            return false;
+        }
+        if source.starts_with("node:") {
+            return true;
+        }
+        let name = source.split('/').next().unwrap_or(source);
+        NODE_ONLY_BUILTINS.contains(&name)
+    }
+
+    // Resolves the module the client entry proxy is required from for the
+    // targeted runtime.
+    fn proxy_module(&self) -> &str {
+        let proxy_module = match self.runtime {
+            Runtime::Edge => self
+                .edge_proxy_module
+                .as_ref()
+                .or(self.proxy_module.as_ref()),
+            Runtime::Nodejs => self.proxy_module.as_ref(),
+        };
+
+        proxy_module
+            .map(|s| s.as_str())
+            .unwrap_or("private-next-rsc-mod-ref-proxy")
+    }
+
+    // An identifier named after `name` for a binding of the generated code.
+    // It's renamed if the prelude or one of the `export_names` uses the name
+    // already, so that the two can't clash.
+    fn free_ident(&self, name: &str, export_names: &[JsWord]) -> Ident {
+        let mut names = IdentNames::default();
+        for stmt in &self.proxy_prelude {
+            stmt.visit_with(&mut names);
+        }
+        names.0.extend(export_names.iter().cloned());
+
+        let mut ident = JsWord::from(name);
+        let mut suffix = 1;
+        while names.0.contains(&ident) {
+            ident = format!("{}{}", name, suffix).into();
+            suffix += 1;
+        }
+        quote_ident!(ident)
+    }
+
+    // Severity of the diagnostics for a disallowed import of `source`.
+    fn severity_for(&self, source: &JsWord) -> Severity {
+        self.source_severity
+            .get(source)
+            .copied()
+            .unwrap_or(self.severity)
+    }
+
+    // Checks that the default export of a client entry can be a component. It
+    // has to run before the module is replaced by the module reference.
+    fn assert_component_default_export(&self, module: &Module) {
+        for item in &module.body {
+            if let ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(export)) = item {
+                if let Some(kind) = non_component_kind(&export.expr) {
+                    self.report(
+                        Severity::Warning,
+                        export.expr.span(),
+                        format!(
+                            "The default export of a client entry should be a component, found {}.",
+                            kind
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn assert_server_graph(&self, imports: &[ModuleImports]) {
+        for import in imports {
+            if self.has_failed() {
+                return;
+            }
             let source = import.source.0.clone();
             if self.invalid_server_imports.contains(&source) {
-                HANDLER.with(|handler| {
-                    handler
-                        .struct_span_err(
-                            import.source.1,
-                            format!(
-                                "Disallowed import of `{}` in the Server Components compilation.",
-                                source
-                            )
-                            .as_str(),
-                        )
-                        .emit()
-                })
+                self.report(
+                    self.severity_for(&source),
+                    import.source.1,
+                    format!(
+                        "Disallowed {} of `{}` in the Server Components compilation.",
+                        import.kind.describe(),
+                        source
+                    ),
+                )
+            }
+            self.assert_banned_source_patterns(import, "Server Components");
+            if self.is_internal_package(&source) {
+                self.report(
+                    self.severity_for(&source),
+                    import.source.1,
+                    format!(
+                        "Disallowed {} of `{}` in the Server Components compilation, it's \
+                         internal to the React Server Components runtime.",
+                        import.kind.describe(),
+                        source
+                    ),
+                )
             }
-            if source == *"react" {
+            if self.is_react_source(&source) {
                 for specifier in &import.specifiers {
-                    if self.invalid_server_react_apis.contains(&specifier.0) {
-                        HANDLER.with(|handler| {
-                            handler
-                                .struct_span_err(
-                                    specifier.1,
-                                    format!(
-                                        "Disallowed React API `{}` in the Server Components \
-                                         compilation.",
-                                        &specifier.0
-                                    )
-                                    .as_str(),
-                                )
-                                .emit()
-                        })
+                    if self.is_disallowed_api(
+                        &self.invalid_server_react_apis,
+                        &source,
+                        &specifier.0,
+                    ) {
+                        self.report(
+                            self.severity_for(&source),
+                            specifier.1,
+                            format!(
+                                "Disallowed React API `{}` in the Server Components compilation.{}",
+                                &specifier.0,
+                                self.used_in_exports_of(&specifier.2)
+                            ),
+                        )
                     }
                 }
             }
-            if source == *"react-dom" {
+            if self.is_react_dom_source(&source) {
                 for specifier in &import.specifiers {
-                    if self.invalid_server_react_dom_apis.contains(&specifier.0) {
-                        HANDLER.with(|handler| {
-                            handler
-                                .struct_span_err(
-                                    specifier.1,
-                                    format!(
-                                        "Disallowed ReactDOM API `{}` in the Server Components \
-                                         compilation.",
-                                        &specifier.0
-                                    )
-                                    .as_str(),
-                                )
-                                .emit()
-                        })
+                    if self.is_disallowed_api(
+                        &self.invalid_server_react_dom_apis,
+                        &source,
+                        &specifier.0,
+                    ) {
+                        self.report(
+                            self.severity_for(&source),
+                            specifier.1,
+                            format!(
+                                "Disallowed ReactDOM API `{}` in the Server Components \
+                                 compilation.{}",
+                                &specifier.0,
+                                self.used_in_exports_of(&specifier.2)
+                            ),
+                        )
                     }
                 }
             }
         }
     }
 
-    fn assert_client_graph(&self, imports: &Vec<ModuleImports>) {
+    fn assert_action_graph(&self, imports: &[ModuleImports]) {
         for import in imports {
+            if self.has_failed() {
+                return;
+            }
+            let source = &import.source.0;
+            if self.invalid_action_imports.contains(source) {
+                self.report(
+                    self.severity_for(source),
+                    import.source.1,
+                    format!(
+                        "Disallowed {} of `{}` in a Server Actions module.",
+                        import.kind.describe(),
+                        source
+                    ),
+                )
+            }
+        }
+    }
+
+    // Reports the imports of client entries by a client entry, which don't
+    // start a new client boundary.
+    fn assert_no_nested_client_boundary(&self, imports: &[ModuleImports]) {
+        for import in imports {
+            if self.client_entry_sources.contains(&import.source.0) {
+                self.report(
+                    Severity::Warning,
+                    import.source.1,
+                    format!(
+                        "Import of the client entry `{}` by another client entry. It's inside of \
+                         the client boundary already, so its `\"use client\"` directive is only \
+                         needed if Server Components import it too.",
+                        import.source.0
+                    ),
+                )
+            }
+        }
+    }
+
+    fn assert_client_graph(&self, imports: &[ModuleImports]) {
+        for import in imports {
+            if self.has_failed() {
+                return;
+            }
             let source = import.source.0.clone();
-            if self.invalid_client_imports.contains(&source) {
-                HANDLER.with(|handler| {
-                    handler
-                        .struct_span_err(
-                            import.source.1,
-                            format!(
-                                "Disallowed import of `{}` in the Client Components compilation.",
-                                source
-                            )
-                            .as_str(),
-                        )
-                        .emit()
+            if self.invalid_client_imports.contains(&source) || self.is_banned_node_builtin(&source)
+            {
+                self.report(
+                    self.severity_for(&source),
+                    import.source.1,
+                    format!(
+                        "Disallowed {} of `{}` in the Client Components compilation.",
+                        import.kind.describe(),
+                        source
+                    ),
+                )
+            }
+            self.assert_banned_source_patterns(import, "Client Components");
+            if self.server_entry_sources.contains(&source) {
+                self.report(
+                    self.severity_for(&source),
+                    import.source.1,
+                    format!(
+                        "Disallowed {} of the Server Component `{}` in the Client Components \
+                         compilation. Pass it to the Client Component as a prop instead.",
+                        import.kind.describe(),
+                        source
+                    ),
+                )
+            }
+            if self.discouraged_client_imports.contains(&source) {
+                self.report(
+                    Severity::Warning,
+                    import.source.1,
+                    format!(
+                        "Import of `{}` is discouraged in the Client Components compilation. \
+                         Consider moving it to a Server Component.",
+                        source
+                    ),
+                )
+            }
+        }
+    }
+}
+
+// Collects the names of all identifiers, whether they're bindings or not.
+#[derive(Default)]
+struct IdentNames(AHashSet<JsWord>);
+
+impl Visit for IdentNames {
+    noop_visit_type!();
+
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.0.insert(ident.sym.clone());
+    }
+}
+
+// Collects the exports calling one of `bindings` through the imported name.
+// Aliased calls are reported on their own, they're not collected.
+struct ExportUses<'a> {
+    bindings: &'a AHashMap<Id, (JsWord, &'static str)>,
+    current_export: Option<JsWord>,
+    exports: AHashMap<Id, Vec<JsWord>>,
+}
+
+impl ExportUses<'_> {
+    fn visit_in_export<N: VisitWith<Self>>(&mut self, name: Option<JsWord>, node: &N) {
+        self.current_export = name;
+        node.visit_children_with(self);
+        self.current_export = None;
+    }
+}
+
+impl Visit for ExportUses<'_> {
+    noop_visit_type!();
+
+    fn visit_call_expr(&mut self, expr: &CallExpr) {
+        if let (Callee::Expr(callee), Some(export)) = (&expr.callee, &self.current_export) {
+            if let Expr::Ident(ident) = &**callee {
+                if let Some((api, _)) = self.bindings.get(&ident.to_id()) {
+                    if ident.sym == *api {
+                        let exports = self.exports.entry(ident.to_id()).or_default();
+                        if !exports.contains(export) {
+                            exports.push(export.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        expr.visit_children_with(self)
+    }
+
+    fn visit_export_decl(&mut self, export: &ExportDecl) {
+        match &export.decl {
+            Decl::Var(var) => {
+                for decl in &var.decls {
+                    self.visit_in_export(var_export_name(decl), decl);
+                }
+            }
+            decl => self.visit_in_export(decl_export_name(decl), decl),
+        }
+    }
+
+    fn visit_export_default_decl(&mut self, export: &ExportDefaultDecl) {
+        self.visit_in_export(Some(default_export_name(&export.decl)), export);
+    }
+
+    fn visit_export_default_expr(&mut self, export: &ExportDefaultExpr) {
+        self.visit_in_export(Some(js_word!("default")), export);
+    }
+}
+
+// The sentence naming `exports` in a diagnostic, empty if there are none.
+fn used_in_exports(exports: &[JsWord]) -> String {
+    match exports {
+        [] => String::new(),
+        [export] => format!(" Used in export `{}`.", export),
+        exports => format!(
+            " Used in exports {}.",
+            exports
+                .iter()
+                .map(|export| format!("`{}`", export))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+// Name of the export declared by `decl` of an `export const`, unless it's
+// destructured.
+fn var_export_name(decl: &VarDeclarator) -> Option<JsWord> {
+    match &decl.name {
+        Pat::Ident(ident) => Some(ident.id.sym.clone()),
+        _ => None,
+    }
+}
+
+// Name of the export declared by `decl`, for the declarations other than
+// variables.
+fn decl_export_name(decl: &Decl) -> Option<JsWord> {
+    match decl {
+        Decl::Class(class) => Some(class.ident.sym.clone()),
+        Decl::Fn(function) => Some(function.ident.sym.clone()),
+        _ => None,
+    }
+}
+
+// Name of the default export, or the name of its class or function.
+fn default_export_name(decl: &DefaultDecl) -> JsWord {
+    match decl {
+        DefaultDecl::Class(ClassExpr {
+            ident: Some(ident), ..
+        })
+        | DefaultDecl::Fn(FnExpr {
+            ident: Some(ident), ..
+        }) => ident.sym.clone(),
+        _ => js_word!("default"),
+    }
+}
+
+// Names of the named exports of `module`. Type exports don't need a
+// reference and the names of `export *` can't be known, they're left out, as
+// are exports named with a string.
+fn named_export_names(module: &Module) -> Vec<JsWord> {
+    let mut names = vec![];
+    for item in &module.body {
+        match item {
+            ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(export)) => match &export.decl {
+                Decl::Class(class) => names.push(class.ident.sym.clone()),
+                Decl::Fn(function) => names.push(function.ident.sym.clone()),
+                Decl::TsEnum(ts_enum) => names.push(ts_enum.id.sym.clone()),
+                Decl::Var(var) => names.extend(
+                    var.decls
+                        .iter()
+                        .flat_map(|decl| find_pat_ids::<_, Id>(&decl.name))
+                        .map(|id| id.0),
+                ),
+                _ => {}
+            },
+            ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(export)) if !export.type_only => {
+                for specifier in &export.specifiers {
+                    let exported = match specifier {
+                        ExportSpecifier::Named(ExportNamedSpecifier {
+                            orig,
+                            exported,
+                            is_type_only: false,
+                            ..
+                        }) => exported.as_ref().unwrap_or(orig),
+                        ExportSpecifier::Namespace(ExportNamespaceSpecifier { name, .. }) => name,
+                        _ => continue,
+                    };
+                    if let ModuleExportName::Ident(ident) = exported {
+                        if &*ident.sym != "default" {
+                            names.push(ident.sym.clone());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+// Describes `expr` if it's a value that can't be a component.
+fn non_component_kind(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::Paren(paren) => non_component_kind(&paren.expr),
+        Expr::Lit(Lit::Num(_)) => Some("a number"),
+        Expr::Lit(Lit::Str(_)) | Expr::Tpl(_) => Some("a string"),
+        Expr::Lit(Lit::Bool(_)) => Some("a boolean"),
+        Expr::Lit(Lit::Null(_)) => Some("`null`"),
+        Expr::Lit(Lit::BigInt(_)) => Some("a bigint"),
+        Expr::Lit(Lit::Regex(_)) => Some("a regular expression"),
+        Expr::Object(_) => Some("an object"),
+        Expr::Array(_) => Some("an array"),
+        _ => None,
+    }
+}
+
+// Parses the statements of a `proxy_prelude` entry. They don't come from the
+// module being transformed, so their spans are dropped. Errors the parser
+// recovered from make the entry invalid too.
+fn parse_proxy_prelude(source: &str) -> Result<Vec<Stmt>, String> {
+    let cm: Lrc<SourceMap> = Default::default();
+    let fm = cm.new_source_file(FileName::Anon, source.into());
+    let mut recovered_errors = vec![];
+    let mut script = parse_file_as_script(
+        &fm,
+        Default::default(),
+        EsVersion::latest(),
+        None,
+        &mut recovered_errors,
+    )
+    .and_then(|script| match recovered_errors.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(script),
+    })
+    .map_err(|err| err.kind().msg().into_owned())?;
+    script.visit_mut_with(&mut DropSpan {
+        preserve_ctxt: false,
+    });
+    Ok(script.body)
+}
+
+// Gives every node the same span.
+struct Respan(Span);
+
+impl VisitMut for Respan {
+    noop_visit_mut_type!();
+
+    fn visit_mut_span(&mut self, span: &mut Span) {
+        *span = self.0;
+    }
+}
+
+// Object.defineProperty(module.exports, "__esModule", { value: true });
+fn esmodule_flag(span: Span) -> ModuleItem {
+    ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+        span,
+        expr: Box::new(Expr::Call(CallExpr {
+            span: DUMMY_SP,
+            callee: Callee::Expr(Box::new(Expr::Member(MemberExpr {
+                span: DUMMY_SP,
+                obj: Box::new(Expr::Ident(quote_ident!("Object"))),
+                prop: MemberProp::Ident(quote_ident!("defineProperty")),
+            }))),
+            args: vec![
+                Expr::Member(MemberExpr {
+                    span: DUMMY_SP,
+                    obj: Box::new(Expr::Ident(quote_ident!("module"))),
+                    prop: MemberProp::Ident(quote_ident!("exports")),
                 })
+                .as_arg(),
+                quote_str!("__esModule").as_arg(),
+                Expr::Object(ObjectLit {
+                    span: DUMMY_SP,
+                    props: vec![PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                        key: PropName::Ident(quote_ident!("value")),
+                        value: Box::new(Expr::Lit(Lit::Bool(Bool {
+                            span: DUMMY_SP,
+                            value: true,
+                        }))),
+                    })))],
+                })
+                .as_arg(),
+            ],
+            type_args: Default::default(),
+        })),
+    }))
+}
+
+// Builds a module reference that only requires the proxy module once a
+// property of `module.exports` is first accessed. Module loaders read
+// `module.exports` itself right after evaluating the module, so that can't
+// be what triggers it:
+//
+// let proxy;
+// module.exports = new Proxy({}, {
+//   get (target, name) {
+//     if (!proxy) {
+//       const { createProxy } = require("private-next-rsc-mod-ref-proxy");
+//       proxy = createProxy("/path/to/file.js");
+//     }
+//     return proxy[name];
+//   }
+// });
+fn lazy_module_ref(
+    span: Span,
+    proxy_ident: Ident,
+    require_proxy: Stmt,
+    create_proxy: Expr,
+) -> Vec<ModuleItem> {
+    let name_ident = quote_ident!("name");
+    let getter = Function {
+        params: ["target", "name"]
+            .iter()
+            .map(|name| Param {
+                span: DUMMY_SP,
+                decorators: vec![],
+                pat: Pat::Ident(quote_ident!(*name).into()),
+            })
+            .collect(),
+        decorators: vec![],
+        span: DUMMY_SP,
+        body: Some(BlockStmt {
+            span: DUMMY_SP,
+            stmts: vec![
+                Stmt::If(IfStmt {
+                    span: DUMMY_SP,
+                    test: Box::new(Expr::Unary(UnaryExpr {
+                        span: DUMMY_SP,
+                        op: op!("!"),
+                        arg: Box::new(Expr::Ident(proxy_ident.clone())),
+                    })),
+                    cons: Box::new(Stmt::Block(BlockStmt {
+                        span: DUMMY_SP,
+                        stmts: vec![
+                            require_proxy,
+                            Stmt::Expr(ExprStmt {
+                                span: DUMMY_SP,
+                                expr: Box::new(Expr::Assign(AssignExpr {
+                                    span: DUMMY_SP,
+                                    left: PatOrExpr::Pat(Box::new(Pat::Ident(
+                                        proxy_ident.clone().into(),
+                                    ))),
+                                    op: op!("="),
+                                    right: Box::new(create_proxy),
+                                })),
+                            }),
+                        ],
+                    })),
+                    alt: None,
+                }),
+                Stmt::Return(ReturnStmt {
+                    span: DUMMY_SP,
+                    arg: Some(Box::new(Expr::Member(MemberExpr {
+                        span: DUMMY_SP,
+                        obj: Box::new(Expr::Ident(proxy_ident.clone())),
+                        prop: MemberProp::Computed(ComputedPropName {
+                            span: DUMMY_SP,
+                            expr: Box::new(Expr::Ident(name_ident)),
+                        }),
+                    }))),
+                }),
+            ],
+        }),
+        is_generator: false,
+        is_async: false,
+        type_params: None,
+        return_type: None,
+    };
+
+    vec![
+        ModuleItem::Stmt(Stmt::Decl(Decl::Var(VarDecl {
+            span,
+            kind: VarDeclKind::Let,
+            decls: vec![VarDeclarator {
+                span,
+                name: Pat::Ident(proxy_ident.into()),
+                init: None,
+                definite: false,
+            }],
+            declare: false,
+        }))),
+        ModuleItem::Stmt(Stmt::Expr(ExprStmt {
+            span,
+            expr: Box::new(Expr::Assign(AssignExpr {
+                span,
+                left: PatOrExpr::Expr(Box::new(Expr::Member(MemberExpr {
+                    span: DUMMY_SP,
+                    obj: Box::new(Expr::Ident(quote_ident!("module"))),
+                    prop: MemberProp::Ident(quote_ident!("exports")),
+                }))),
+                op: op!("="),
+                right: Box::new(Expr::New(NewExpr {
+                    span: DUMMY_SP,
+                    callee: Box::new(Expr::Ident(quote_ident!("Proxy"))),
+                    args: Some(vec![
+                        Expr::Object(ObjectLit {
+                            span: DUMMY_SP,
+                            props: vec![],
+                        })
+                        .as_arg(),
+                        Expr::Object(ObjectLit {
+                            span: DUMMY_SP,
+                            props: vec![PropOrSpread::Prop(Box::new(Prop::Method(MethodProp {
+                                key: PropName::Ident(quote_ident!("get")),
+                                function: getter.into(),
+                            })))],
+                        })
+                        .as_arg(),
+                    ]),
+                    type_args: Default::default(),
+                })),
+            })),
+        })),
+    ]
+}
+
+// Matches a path against an `exclude` entry: a glob if it contains `*`,
+// otherwise a plain prefix.
+fn is_excluded_path(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('*') {
+        return path.starts_with(pattern);
+    }
+
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut rest = path;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
             }
         }
     }
+    true
+}
+
+impl<C: Comments> ReactServerComponents<C> {
+    fn new(
+        filename: FileName,
+        options: Options,
+        comments: C,
+        analysis: Rc<RefCell<Analysis>>,
+    ) -> Self {
+        // Windows paths are normalized so that the generated code is the same
+        // on every platform.
+        let filepath = filename.to_string().replace('\\', "/");
+        let is_rewritten_extension = match &options.rewrite_extensions {
+            Some(extensions) => {
+                let extension = Path::new(&filepath)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .unwrap_or_default();
+                extensions
+                    .iter()
+                    .any(|allowed| allowed.trim_start_matches('.') == extension)
+            }
+            None => true,
+        };
+        let is_excluded = options
+            .exclude
+            .unwrap_or_default()
+            .iter()
+            .any(|pattern| is_excluded_path(pattern, &filepath));
+        let mut invalid_options = vec![];
+        let banned_source_patterns = options
+            .banned_source_patterns
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|pattern| match Regex::new(&pattern) {
+                Ok(regex) => Some(regex),
+                Err(err) => {
+                    invalid_options.push(format!(
+                        "Invalid pattern `{}` in `bannedSourcePatterns`: {}",
+                        pattern, err
+                    ));
+                    None
+                }
+            })
+            .collect();
+        let proxy_prelude = options
+            .proxy_prelude
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|source| match parse_proxy_prelude(source) {
+                Ok(stmts) => Some(stmts),
+                Err(err) => {
+                    invalid_options.push(format!(
+                        "Invalid statements `{}` in `proxyPrelude`: {}",
+                        source, err
+                    ));
+                    None
+                }
+            })
+            .flatten()
+            .collect();
+        let preset = options.preset.unwrap_or(Preset::NextAppRouter);
+        let transform = ReactServerComponents {
+            check_both_graphs: false,
+            is_server: options.is_server,
+            is_excluded,
+            comments,
+            analysis,
+            filepath,
+            invalid_server_imports: ban_list(
+                options.invalid_server_imports,
+                preset.invalid_server_imports(),
+            ),
+            invalid_client_imports: ban_list(
+                options.invalid_client_imports,
+                preset.invalid_client_imports(),
+            ),
+            banned_source_patterns,
+            internal_packages: options
+                .internal_packages
+                .map(|packages| packages.into_iter().map(JsWord::from).collect())
+                .unwrap_or_else(|| vec![JsWord::from("react-server-dom-webpack")]),
+            invalid_action_imports: options
+                .invalid_action_imports
+                .unwrap_or_default()
+                .into_iter()
+                .map(JsWord::from)
+                .collect(),
+            discouraged_client_imports: options
+                .discouraged_client_imports
+                .unwrap_or_default()
+                .into_iter()
+                .map(JsWord::from)
+                .collect(),
+            client_entry_sources: options
+                .client_entry_sources
+                .unwrap_or_default()
+                .into_iter()
+                .map(JsWord::from)
+                .collect(),
+            server_entry_sources: options
+                .server_entry_sources
+                .unwrap_or_default()
+                .into_iter()
+                .map(JsWord::from)
+                .collect(),
+            react_aliases: options
+                .react_aliases
+                .unwrap_or_default()
+                .into_iter()
+                .map(JsWord::from)
+                .collect(),
+            lazy: options.lazy,
+            esm: options.esm,
+            alias_map: options
+                .alias_map
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(from, to)| (JsWord::from(from), JsWord::from(to)))
+                .collect(),
+            allowed_server_specifiers: options
+                .allowed_server_specifiers
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(source, specifiers)| {
+                    (
+                        JsWord::from(source),
+                        specifiers.into_iter().map(JsWord::from).collect(),
+                    )
+                })
+                .collect(),
+            runtime: options.runtime.unwrap_or(Runtime::Nodejs),
+            proxy_module: options.proxy_module,
+            edge_proxy_module: options.edge_proxy_module,
+            proxy_prelude,
+            ban_node_builtins_on_client: options.ban_node_builtins_on_client,
+            marker_as_export: options.marker_as_export,
+            mark_esmodule: options.mark_esmodule,
+            proxy_arg_object: options.proxy_arg_object,
+            client_directives: options.client_directives.unwrap_or_default(),
+            server_directives: options.server_directives.unwrap_or_default(),
+            warn_non_component_client_default: options.warn_non_component_client_default,
+            is_rewritten_extension,
+            verbose: options.verbose,
+            disallowed_api_bindings: Default::default(),
+            disallowed_api_exports: Default::default(),
+            check_top_level_hooks: options.check_top_level_hooks,
+            collect_hook_usage: options.collect_hook_usage,
+            strict_directive_prologue: options.strict_directive_prologue,
+            lenient_directive_position: options.lenient_directive_position,
+            severity: options.severity.unwrap_or(Severity::Error),
+            source_severity: options
+                .source_severity
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(source, severity)| (JsWord::from(source), severity))
+                .collect(),
+            hook_bindings: Default::default(),
+            function_depth: 0,
+            current_export: None,
+            is_config_all: false,
+            fail_fast: options.fail_fast,
+            warn_nested_client_boundary: options.warn_nested_client_boundary,
+            file_start_pos: BytePos(0),
+            invalid_server_react_dom_apis: ban_list(
+                options.invalid_server_react_dom_apis,
+                preset.invalid_server_react_dom_apis(),
+            ),
+            invalid_server_react_apis: ban_list(
+                options.invalid_server_react_apis,
+                preset.invalid_server_react_apis(),
+            ),
+        };
+        if transform.marker_as_export && !transform.esm {
+            invalid_options.push(
+                "`markerAsExport` requires `esm`, the CommonJS proxies can't be marked because \
+                 they don't allow setting properties on `module.exports`."
+                    .into(),
+            );
+        }
+        for message in invalid_options {
+            transform.report(Severity::Error, DUMMY_SP, message);
+        }
+        transform
+    }
 }
 
 pub fn server_components<C: Comments>(
@@ -302,40 +2303,132 @@ pub fn server_components<C: Comments>(
     config: Config,
     comments: C,
 ) -> impl Fold + VisitMut {
-    let is_server: bool = match config {
-        Config::WithOptions(x) => x.is_server,
-        _ => true,
-    };
-    as_folder(ReactServerComponents {
-        is_server,
-        comments,
-        filepath: filename.to_string(),
-        invalid_server_imports: vec![
-            JsWord::from("client-only"),
-            JsWord::from("react-dom/client"),
-            JsWord::from("react-dom/server"),
-        ],
-        invalid_client_imports: vec![JsWord::from("server-only")],
-        invalid_server_react_dom_apis: vec![
-            JsWord::from("findDOMNode"),
-            JsWord::from("flushSync"),
-            JsWord::from("unstable_batchedUpdates"),
-        ],
-        invalid_server_react_apis: vec![
-            JsWord::from("Component"),
-            JsWord::from("createContext"),
-            JsWord::from("createFactory"),
-            JsWord::from("PureComponent"),
-            JsWord::from("useDeferredValue"),
-            JsWord::from("useEffect"),
-            JsWord::from("useImperativeHandle"),
-            JsWord::from("useInsertionEffect"),
-            JsWord::from("useLayoutEffect"),
-            JsWord::from("useReducer"),
-            JsWord::from("useRef"),
-            JsWord::from("useState"),
-            JsWord::from("useSyncExternalStore"),
-            JsWord::from("useTransition"),
-        ],
-    })
+    server_components_with_analysis(filename, BytePos(0), config, comments, Default::default())
+}
+
+/// Same as `server_components`, but records what the transform found and
+/// changed into `analysis`. `file_start_pos` is the `start_pos` of the file
+/// in the source map, the byte ranges of the diagnostics are relative to it.
+pub fn server_components_with_analysis<C: Comments>(
+    filename: FileName,
+    file_start_pos: BytePos,
+    config: Config,
+    comments: C,
+    analysis: Rc<RefCell<Analysis>>,
+) -> impl Fold + VisitMut {
+    let is_config_all = matches!(config, Config::All(_));
+    let mut transform =
+        ReactServerComponents::new(filename, config.into_options(), comments, analysis);
+    transform.is_config_all = is_config_all;
+    transform.file_start_pos = file_start_pos;
+    as_folder(transform)
+}
+
+/// Checks `module` for both compilations in a single traversal: client
+/// entries are checked as part of the Client Components compilation, every
+/// other module as part of the Server Components compilation. `is_server` is
+/// ignored. Directives are removed like in the regular transform, but client
+/// entries are not rewritten. `comments` are the comments of `module`, and
+/// `file_start_pos` is the `start_pos` of its file like for
+/// `server_components_with_analysis`.
+pub fn check_module<C: Comments>(
+    filename: FileName,
+    file_start_pos: BytePos,
+    options: Options,
+    comments: C,
+    module: &mut Module,
+) -> Analysis {
+    let analysis: Rc<RefCell<Analysis>> = Default::default();
+    let mut transform = ReactServerComponents::new(filename, options, comments, analysis.clone());
+    transform.check_both_graphs = true;
+    transform.file_start_pos = file_start_pos;
+    module.visit_mut_with(&mut transform);
+
+    analysis.take()
+}
+
+/// The outcome of `process_module`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProcessResult {
+    /// `None` if the module was skipped.
+    pub classification: Option<Classification>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Whether the body of the client entry was replaced by the module
+    /// reference.
+    pub rewritten: bool,
+}
+
+/// Runs the transform on `module` and returns what it found, without having
+/// to set up the folder and the analysis. The diagnostics are still emitted
+/// through the handler if one is set. `comments` are the comments of
+/// `module`, and `file_start_pos` is the `start_pos` of its file like for
+/// `server_components_with_analysis`.
+pub fn process_module<C: Comments>(
+    module: &mut Module,
+    filename: FileName,
+    file_start_pos: BytePos,
+    options: Options,
+    comments: C,
+) -> ProcessResult {
+    let analysis: Rc<RefCell<Analysis>> = Default::default();
+    let mut transform = ReactServerComponents::new(filename, options, comments, analysis.clone());
+    transform.file_start_pos = file_start_pos;
+    module.visit_mut_with(&mut transform);
+
+    let analysis = analysis.take();
+    ProcessResult {
+        classification: analysis.classification,
+        rewritten: analysis
+            .changes
+            .iter()
+            .any(|change| matches!(change, Change::BodyReplaced { .. })),
+        diagnostics: analysis.diagnostics,
+    }
+}
+
+/// The options of the transform with their defaults applied, for running the
+/// checks through `check_imports`.
+pub struct EffectiveConfig {
+    transform: ReactServerComponents<SingleThreadedComments>,
+    errors: Vec<Diagnostic>,
+}
+
+impl EffectiveConfig {
+    pub fn new(filename: FileName, options: Options) -> Self {
+        let transform = ReactServerComponents::new(
+            filename,
+            options,
+            SingleThreadedComments::default(),
+            Default::default(),
+        );
+        // The errors of invalid options are kept apart, so that they're not
+        // attributed to the first import list that is checked.
+        let errors = std::mem::take(&mut transform.analysis.borrow_mut().diagnostics);
+        EffectiveConfig { transform, errors }
+    }
+
+    /// The errors of invalid options, e.g. a `bannedSourcePatterns` entry that
+    /// isn't a valid regex.
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+}
+
+/// Checks an import list that was collected by other means, e.g. by a tool
+/// that already has the module graph, without traversing a module. Runs the
+/// same checks as the transform does for the imports of a module in the
+/// Server or Client Components compilation. The sources of `imports` have to
+/// be resolved through `alias_map` already, they're checked as they are.
+pub fn check_imports(
+    imports: &[ModuleImports],
+    is_server: bool,
+    config: &EffectiveConfig,
+) -> Vec<Diagnostic> {
+    if is_server {
+        config.transform.assert_server_graph(imports);
+    } else {
+        config.transform.assert_client_graph(imports);
+    }
+
+    std::mem::take(&mut config.transform.analysis.borrow_mut().diagnostics)
 }
@@ -0,0 +1,58 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use next_swc::react_server_components::{
+    server_components_with_analysis, Analysis, Classification, Config, Severity,
+};
+
+use swc_core::{
+    common::{comments::SingleThreadedComments, errors::HANDLER, FileName},
+    ecma::ast::EsVersion,
+    ecma::parser::parse_file_as_module,
+    ecma::visit::VisitMutWith,
+};
+
+// The deprecation of `Config::All` is reported once per process, this test
+// has a binary of its own so that no other test can report it first.
+#[test]
+fn should_warn_once_per_process_about_the_boolean_config() {
+    let run = || {
+        testing::run_test(false, |cm, handler| {
+            let fm = cm.new_source_file(
+                FileName::Real("/some-project/src/some-file.js".into()),
+                "export default function Page() {}\n".into(),
+            );
+            let mut module = parse_file_as_module(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap();
+            let analysis: Rc<RefCell<Analysis>> = Default::default();
+            HANDLER.set(handler, || {
+                module.visit_mut_with(&mut server_components_with_analysis(
+                    FileName::Real("/some-project/src/some-file.js".into()),
+                    fm.start_pos,
+                    Config::All(true),
+                    SingleThreadedComments::default(),
+                    analysis.clone(),
+                ))
+            });
+            Ok(analysis.take())
+        })
+        .unwrap()
+    };
+
+    let first = run();
+    assert_eq!(first.classification, Some(Classification::Module));
+    assert_eq!(first.diagnostics.len(), 1);
+    assert_eq!(first.diagnostics[0].severity, Severity::Warning);
+    assert!(first.diagnostics[0]
+        .message
+        .starts_with("Configuring the Server Components transform with a boolean is deprecated"));
+
+    let second = run();
+    assert!(second.diagnostics.is_empty());
+}
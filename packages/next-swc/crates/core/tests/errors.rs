@@ -1,6 +1,8 @@
 use next_swc::{
-    disallow_re_export_all_in_page::disallow_re_export_all_in_page, next_dynamic::next_dynamic,
-    next_ssg::next_ssg, react_server_components::server_components,
+    disallow_re_export_all_in_page::disallow_re_export_all_in_page,
+    next_dynamic::next_dynamic,
+    next_ssg::next_ssg,
+    react_server_components::{server_components, Config, Options},
 };
 use std::path::PathBuf;
 use swc_core::{
@@ -57,17 +59,14 @@ fn next_ssg_errors(input: PathBuf) {
     );
 }
 
-#[fixture("tests/errors/react-server-components/server-graph/**/input.js")]
-fn react_server_components_server_graph_errors(input: PathBuf) {
+fn rsc_fixture(input: PathBuf, options: Options) {
     let output = input.parent().unwrap().join("output.js");
     test_fixture_allowing_error(
         syntax(),
         &|tr| {
             server_components(
                 FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
-                next_swc::react_server_components::Config::WithOptions(
-                    next_swc::react_server_components::Options { is_server: true },
-                ),
+                Config::WithOptions(options.clone()),
                 tr.comments.as_ref().clone(),
             )
         },
@@ -76,17 +75,76 @@ fn react_server_components_server_graph_errors(input: PathBuf) {
     );
 }
 
+#[fixture("tests/errors/react-server-components/server-graph/**/input.js")]
+fn react_server_components_server_graph_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+}
+
 #[fixture("tests/errors/react-server-components/client-graph/**/input.js")]
 fn react_server_components_client_graph_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/discouraged-client-imports/**/input.js")]
+fn react_server_components_discouraged_client_imports_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            discouraged_client_imports: Some(vec!["heavy-data-lib".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/nested-directive/**/input.ts")]
+fn react_server_components_nested_directive_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.ts");
+    test_fixture_allowing_error(
+        Syntax::Typescript(Default::default()),
+        &|tr| {
+            server_components(
+                FileName::Real(PathBuf::from("/some-project/src/some-file.ts")),
+                Config::WithOptions(Options {
+                    is_server: true,
+                    ..Default::default()
+                }),
+                tr.comments.as_ref().clone(),
+            )
+        },
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/react-server-components/exclude/excluded/**/input.js")]
+fn react_server_components_exclude_excluded_errors(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
     test_fixture_allowing_error(
         syntax(),
         &|tr| {
             server_components(
-                FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
-                next_swc::react_server_components::Config::WithOptions(
-                    next_swc::react_server_components::Options { is_server: false },
-                ),
+                FileName::Real(PathBuf::from("/some-project/src/generated/some-file.js")),
+                Config::WithOptions(Options {
+                    is_server: true,
+                    exclude: Some(vec![
+                        "/some-project/src/generated/*.js".into(),
+                        "/some-project/vendor/".into(),
+                    ]),
+                    ..Default::default()
+                }),
                 tr.comments.as_ref().clone(),
             )
         },
@@ -94,3 +152,203 @@ fn react_server_components_client_graph_errors(input: PathBuf) {
         &output,
     );
 }
+
+#[fixture("tests/errors/react-server-components/exclude/included/**/input.js")]
+fn react_server_components_exclude_included_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            exclude: Some(vec![
+                "/some-project/src/generated/*.js".into(),
+                "/some-project/vendor/".into(),
+            ]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/alias-map/**/input.js")]
+fn react_server_components_alias_map_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            alias_map: Some(
+                vec![("@/react-dom-server".into(), "react-dom/server".into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/allowed-server-specifiers/**/input.js")]
+fn react_server_components_allowed_server_specifiers_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            allowed_server_specifiers: Some(
+                vec![("react".into(), vec!["useRef".into()])]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/top-level-hooks/**/input.js")]
+fn react_server_components_top_level_hooks_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            check_top_level_hooks: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/invalid-action-imports/**/input.js")]
+fn react_server_components_invalid_action_imports_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            invalid_action_imports: Some(vec!["analytics-sdk".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/react-aliases/**/input.js")]
+fn react_server_components_react_aliases_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            react_aliases: Some(vec!["preact/compat".into()]),
+            allowed_server_specifiers: Some(
+                vec![("react".into(), vec!["useRef".into()])]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/node-builtins/**/input.js")]
+fn react_server_components_node_builtins_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            ban_node_builtins_on_client: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/source-severity/**/input.js")]
+fn react_server_components_source_severity_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            source_severity: Some(
+                vec![(
+                    "react-dom/server".into(),
+                    next_swc::react_server_components::Severity::Warning,
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/internal-packages/**/input.js")]
+fn react_server_components_internal_packages_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            internal_packages: Some(vec!["react-server-dom-vite".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/non-component-client-default/**/input.js")]
+fn react_server_components_non_component_client_default_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            warn_non_component_client_default: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/rewrite-extensions/**/input.js")]
+fn react_server_components_rewrite_extensions_errors(input: PathBuf) {
+    let output = input.parent().unwrap().join("output.js");
+    test_fixture_allowing_error(
+        syntax(),
+        &|tr| {
+            server_components(
+                FileName::Real(PathBuf::from("/some-project/src/some-file.mdx")),
+                Config::WithOptions(Options {
+                    is_server: true,
+                    rewrite_extensions: Some(vec!["js".into(), ".tsx".into()]),
+                    ..Default::default()
+                }),
+                tr.comments.as_ref().clone(),
+            )
+        },
+        &input,
+        &output,
+    );
+}
+
+#[fixture("tests/errors/react-server-components/banned-source-patterns/**/input.js")]
+fn react_server_components_banned_source_patterns_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            banned_source_patterns: Some(vec!["^@internal/".into(), "^legacy-.*-utils$".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/strict-directive-prologue/**/input.js")]
+fn react_server_components_strict_directive_prologue_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            strict_directive_prologue: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/errors/react-server-components/server-entry-sources/**/input.js")]
+fn react_server_components_server_entry_sources_errors(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            server_entry_sources: Some(vec!["./server-list".into()]),
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,5 @@
+import "@/react-dom-server"
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,4 @@
+import "@/react-dom-server";
+export default function() {
+    return null;
+}
@@ -0,0 +1,5 @@
+import { useRef, useState } from 'react'
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,4 @@
+import { useRef, useState } from 'react';
+export default function() {
+    return null;
+}
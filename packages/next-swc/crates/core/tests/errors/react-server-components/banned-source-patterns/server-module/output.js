@@ -0,0 +1,7 @@
+import { db } from '@internal/db';
+import { track } from '@internal/analytics/server';
+import format from 'legacy-date-utils';
+import { cn } from './utils';
+export default function() {
+    return cn(db, track, format);
+}
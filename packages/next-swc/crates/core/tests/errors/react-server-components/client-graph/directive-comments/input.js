@@ -0,0 +1,8 @@
+// Comment on the first directive.
+"use client";
+// Comment on the second directive.
+"use client";
+// Comment on the export.
+export default function () {
+  return null;
+}
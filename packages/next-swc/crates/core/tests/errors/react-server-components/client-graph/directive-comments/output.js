@@ -0,0 +1,6 @@
+// Comment on the first directive.
+// Comment on the second directive.
+// Comment on the export.
+export default function() {
+    return null;
+}
@@ -0,0 +1 @@
+export * as s from 'server-only';
@@ -0,0 +1,5 @@
+import { query } from "heavy-data-lib"
+
+export default function () {
+  return query();
+}
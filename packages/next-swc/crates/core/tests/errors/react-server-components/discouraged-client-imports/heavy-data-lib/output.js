@@ -0,0 +1,4 @@
+import { query } from "heavy-data-lib";
+export default function() {
+    return query();
+}
@@ -0,0 +1,5 @@
+"client";
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,4 @@
+"client";
+export default function() {
+    return null;
+}
@@ -0,0 +1,4 @@
+import "client-only";
+export default function() {
+    return null;
+}
@@ -0,0 +1,5 @@
+import "client-only"
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,6 @@
+import { createFromFetch } from 'react-server-dom-vite/client'
+import { renderToPipeableStream } from 'react-server-dom-webpack/server'
+
+export default function () {
+  return createFromFetch || renderToPipeableStream;
+}
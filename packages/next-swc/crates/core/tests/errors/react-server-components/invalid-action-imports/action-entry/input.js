@@ -0,0 +1,7 @@
+"use server";
+
+import { track } from "analytics-sdk"
+
+export async function submit() {
+  track("submit");
+}
@@ -0,0 +1,5 @@
+import { track } from "analytics-sdk"
+
+export async function submit() {
+  track("submit");
+}
@@ -0,0 +1,4 @@
+import { track } from "analytics-sdk";
+export async function submit() {
+    track("submit");
+}
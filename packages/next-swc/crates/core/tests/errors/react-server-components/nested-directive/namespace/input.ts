@@ -0,0 +1,9 @@
+namespace Components {
+  "use client";
+
+  export const value = 1;
+}
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,6 @@
+import fs from 'fs'
+import { Buffer } from 'buffer'
+
+export default function () {
+  return null;
+}
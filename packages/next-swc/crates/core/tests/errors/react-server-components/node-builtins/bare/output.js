@@ -0,0 +1,5 @@
+import fs from 'fs';
+import { Buffer } from 'buffer';
+export default function() {
+    return null;
+}
@@ -0,0 +1,5 @@
+import { readFile } from 'node:fs'
+
+export default function () {
+  return null;
+}
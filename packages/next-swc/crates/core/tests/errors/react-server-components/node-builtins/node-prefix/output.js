@@ -0,0 +1,4 @@
+import { readFile } from 'node:fs';
+export default function() {
+    return null;
+}
@@ -0,0 +1,7 @@
+import { useRef } from 'react'
+
+import { useRef as usePreactRef, useState, flushSync } from 'preact/compat'
+
+export default function () {
+  return null;
+}
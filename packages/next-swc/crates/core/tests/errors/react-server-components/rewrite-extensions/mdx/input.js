@@ -0,0 +1,7 @@
+"use client";
+
+import "client-only"
+
+export default function MDXContent() {
+  return null;
+}
@@ -0,0 +1,5 @@
+"use client";
+import "client-only";
+export default function MDXContent() {
+    return null;
+}
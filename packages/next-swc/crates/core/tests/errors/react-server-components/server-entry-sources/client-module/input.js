@@ -0,0 +1,8 @@
+"use client";
+
+import ServerList from './server-list'
+import { format } from './utils'
+
+export default function () {
+  return format(ServerList);
+}
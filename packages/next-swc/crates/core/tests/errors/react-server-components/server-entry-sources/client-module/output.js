@@ -0,0 +1,5 @@
+import ServerList from './server-list';
+import { format } from './utils';
+export default function() {
+    return format(ServerList);
+}
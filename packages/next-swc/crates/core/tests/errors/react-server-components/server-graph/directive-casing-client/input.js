@@ -0,0 +1,5 @@
+"Client";
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,4 @@
+"Client";
+export default function() {
+    return null;
+}
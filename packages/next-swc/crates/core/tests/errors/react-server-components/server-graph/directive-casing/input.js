@@ -0,0 +1,5 @@
+"Use client";
+
+export default function () {
+  return null;
+}
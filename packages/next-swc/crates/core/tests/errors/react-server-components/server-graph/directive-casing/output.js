@@ -0,0 +1,4 @@
+"Use client";
+export default function() {
+    return null;
+}
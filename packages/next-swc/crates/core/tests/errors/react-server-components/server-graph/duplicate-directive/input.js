@@ -0,0 +1,6 @@
+"use client";
+"use client";
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,5 @@
+import { createFromFetch } from 'react-server-dom-webpack/client'
+
+export default function () {
+  return createFromFetch;
+}
@@ -0,0 +1,4 @@
+import { createFromFetch } from 'react-server-dom-webpack/client';
+export default function() {
+    return createFromFetch;
+}
@@ -0,0 +1,7 @@
+import 'a'
+
+"use client";
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,5 @@
+import 'a';
+"use client";
+export default function() {
+    return null;
+}
@@ -0,0 +1,6 @@
+import { useState as useS } from 'react'
+
+export default function () {
+  const [state] = useS(0);
+  return state;
+}
@@ -0,0 +1,6 @@
+import { createContext, useState as useS } from 'react'
+
+export function Page() {
+  const [state] = useS(createContext(null));
+  return state;
+}
@@ -0,0 +1,5 @@
+import { createContext } from 'react'
+
+export function Page() {
+  return createContext(null);
+}
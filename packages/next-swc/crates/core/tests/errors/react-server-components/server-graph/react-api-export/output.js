@@ -0,0 +1,4 @@
+import { createContext } from 'react';
+export function Page() {
+    return createContext(null);
+}
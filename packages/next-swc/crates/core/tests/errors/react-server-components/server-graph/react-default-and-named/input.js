@@ -0,0 +1,5 @@
+import React, { useState, cache } from 'react'
+
+export default function () {
+  return React.createElement('div', null, cache);
+}
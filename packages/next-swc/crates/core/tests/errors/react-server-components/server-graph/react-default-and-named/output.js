@@ -0,0 +1,4 @@
+import React, { useState, cache } from 'react';
+export default function() {
+    return React.createElement('div', null, cache);
+}
@@ -0,0 +1,5 @@
+import ReactDOMServer from "react-dom/server"
+
+export default function () {
+  return ReactDOMServer;
+}
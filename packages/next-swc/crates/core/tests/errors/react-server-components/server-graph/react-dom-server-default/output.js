@@ -0,0 +1,4 @@
+import ReactDOMServer from "react-dom/server";
+export default function() {
+    return ReactDOMServer;
+}
@@ -0,0 +1,5 @@
+import { renderToString } from "react-dom/server"
+
+export default function () {
+  return renderToString;
+}
@@ -0,0 +1,4 @@
+import { renderToString } from "react-dom/server";
+export default function() {
+    return renderToString;
+}
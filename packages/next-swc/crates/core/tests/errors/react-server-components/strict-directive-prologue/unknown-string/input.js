@@ -0,0 +1,7 @@
+"foo";
+
+"use client";
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,5 @@
+"foo";
+"use client";
+export default function() {
+    return null;
+}
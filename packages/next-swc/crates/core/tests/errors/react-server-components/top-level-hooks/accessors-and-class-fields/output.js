@@ -0,0 +1,18 @@
+import { useState } from 'react';
+const store = {
+    get state () {
+        return useState(0);
+    },
+    set state (value) {
+        useState(value);
+    }
+};
+class Store {
+    state = useState(0);
+}
+export default function() {
+    return [
+        store,
+        Store
+    ];
+}
@@ -0,0 +1,6 @@
+import { useState } from 'react'
+
+export default function () {
+  const [state] = useState(0);
+  return state;
+}
@@ -0,0 +1,8 @@
+import { useState } from 'react'
+
+const [initial] = useState(0)
+
+export default function () {
+  const [state] = useState(initial);
+  return state;
+}
@@ -0,0 +1,6 @@
+import { useState } from 'react';
+const [initial] = useState(0);
+export default function() {
+    const [state] = useState(initial);
+    return state;
+}
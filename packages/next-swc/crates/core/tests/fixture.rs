@@ -4,7 +4,7 @@ use next_swc::{
     next_ssg::next_ssg,
     page_config::page_config_test,
     react_remove_properties::remove_properties,
-    react_server_components::server_components,
+    react_server_components::{server_components, Config, Options},
     relay::{relay, Config as RelayConfig, RelayLanguageConfig},
     remove_console::remove_console,
     shake_exports::{shake_exports, Config as ShakeExportsConfig},
@@ -211,17 +211,14 @@ fn shake_exports_fixture_default(input: PathBuf) {
     );
 }
 
-#[fixture("tests/fixture/react-server-components/server-graph/**/input.js")]
-fn react_server_components_server_graph_fixture(input: PathBuf) {
+fn rsc_fixture(input: PathBuf, options: Options) {
     let output = input.parent().unwrap().join("output.js");
     test_fixture(
         syntax(),
         &|tr| {
             server_components(
                 FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
-                next_swc::react_server_components::Config::WithOptions(
-                    next_swc::react_server_components::Options { is_server: true },
-                ),
+                Config::WithOptions(options.clone()),
                 tr.comments.as_ref().clone(),
             )
         },
@@ -230,17 +227,163 @@ fn react_server_components_server_graph_fixture(input: PathBuf) {
     );
 }
 
+#[fixture("tests/fixture/react-server-components/server-graph/**/input.js")]
+fn react_server_components_server_graph_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+}
+
 #[fixture("tests/fixture/react-server-components/client-graph/**/input.js")]
 fn react_server_components_client_graph_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/runtime-nodejs/**/input.js")]
+fn react_server_components_runtime_nodejs_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            runtime: Some(next_swc::react_server_components::Runtime::Nodejs),
+            edge_proxy_module: Some("private-next-rsc-mod-ref-proxy/edge".into()),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/runtime-edge/**/input.js")]
+fn react_server_components_runtime_edge_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            runtime: Some(next_swc::react_server_components::Runtime::Edge),
+            edge_proxy_module: Some("private-next-rsc-mod-ref-proxy/edge".into()),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/lazy/**/input.js")]
+fn react_server_components_lazy_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            lazy: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/empty-source/**/input.js")]
+fn react_server_components_empty_source_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: false,
+            discouraged_client_imports: Some(vec!["".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/strict-directive-prologue/**/input.js")]
+fn react_server_components_strict_directive_prologue_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            strict_directive_prologue: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/esm/**/input.js")]
+fn react_server_components_esm_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            esm: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/proxy-prelude/**/input.js")]
+fn react_server_components_proxy_prelude_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            proxy_prelude: Some(vec!["console.log(\"client entry\");".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/lenient-directive-position/**/input.js")]
+fn react_server_components_lenient_directive_position_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            lenient_directive_position: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/marker-as-export/**/input.js")]
+fn react_server_components_marker_as_export_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            esm: true,
+            marker_as_export: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/non-component-client-default/**/input.js")]
+fn react_server_components_non_component_client_default_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            warn_non_component_client_default: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/windows-path/**/input.js")]
+fn react_server_components_windows_path_fixture(input: PathBuf) {
     let output = input.parent().unwrap().join("output.js");
     test_fixture(
         syntax(),
         &|tr| {
             server_components(
-                FileName::Real(PathBuf::from("/some-project/src/some-file.js")),
-                next_swc::react_server_components::Config::WithOptions(
-                    next_swc::react_server_components::Options { is_server: false },
-                ),
+                FileName::Real(PathBuf::from("C:\\some-project\\src\\some-file.js")),
+                Config::WithOptions(Options {
+                    is_server: true,
+                    ..Default::default()
+                }),
                 tr.comments.as_ref().clone(),
             )
         },
@@ -248,3 +391,51 @@ fn react_server_components_client_graph_fixture(input: PathBuf) {
         &output,
     );
 }
+
+#[fixture("tests/fixture/react-server-components/rewrite-extensions/**/input.js")]
+fn react_server_components_rewrite_extensions_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            rewrite_extensions: Some(vec!["js".into(), ".tsx".into()]),
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/mark-esmodule/**/input.js")]
+fn react_server_components_mark_esmodule_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            mark_esmodule: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/proxy-arg-object/**/input.js")]
+fn react_server_components_proxy_arg_object_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            proxy_arg_object: true,
+            ..Default::default()
+        },
+    );
+}
+
+#[fixture("tests/fixture/react-server-components/custom-directives/**/input.js")]
+fn react_server_components_custom_directives_fixture(input: PathBuf) {
+    rsc_fixture(
+        input,
+        Options {
+            is_server: true,
+            client_directives: Some(vec!["use edge-client".into()]),
+            ..Default::default()
+        },
+    );
+}
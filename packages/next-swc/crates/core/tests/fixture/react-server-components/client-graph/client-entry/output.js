@@ -1,6 +1,8 @@
 // This is a comment.
 "use strict";
-// This is a comment.
+/**
+ * This is a comment.
+ */ // This is a comment.
 "foo";
 import "fs";
 "client";
@@ -0,0 +1 @@
+// A client entry without any code.
@@ -0,0 +1,5 @@
+"use edge-client";
+
+export default function () {
+  return null;
+}
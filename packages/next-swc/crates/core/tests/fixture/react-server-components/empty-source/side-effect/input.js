@@ -0,0 +1,5 @@
+import '';
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,3 @@
+/* __next_internal_client_entry_do_not_use__ */ import { createProxy } from "private-next-rsc-mod-ref-proxy";
+const proxy = createProxy("/some-project/src/some-file.js");
+export default proxy;
@@ -0,0 +1,16 @@
+"client";
+
+import { helper } from "./helper";
+
+export function Button() {
+  return helper();
+}
+
+export const a = 1,
+  { b, c: [d] } = {};
+
+export { helper, helper as proxy };
+
+export default function () {
+  return null;
+}
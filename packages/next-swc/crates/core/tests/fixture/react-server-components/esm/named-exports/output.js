@@ -0,0 +1,9 @@
+/* __next_internal_client_entry_do_not_use__ */ import { createProxy } from "private-next-rsc-mod-ref-proxy";
+const proxy1 = createProxy("/some-project/src/some-file.js");
+export default proxy1;
+export const Button = proxy1.Button;
+export const a = proxy1.a;
+export const b = proxy1.b;
+export const d = proxy1.d;
+export const helper = proxy1.helper;
+export const proxy = proxy1.proxy;
@@ -0,0 +1,10 @@
+/* __next_internal_client_entry_do_not_use__ */ let proxy;
+module.exports = new Proxy({}, {
+    get (target, name) {
+        if (!proxy) {
+            const { createProxy  } = require("private-next-rsc-mod-ref-proxy");
+            proxy = createProxy("/some-project/src/some-file.js");
+        }
+        return proxy[name];
+    }
+});
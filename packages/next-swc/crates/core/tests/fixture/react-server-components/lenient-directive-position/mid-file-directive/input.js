@@ -0,0 +1,7 @@
+import { format } from './utils'
+
+"use client";
+
+export default function () {
+  return format(null);
+}
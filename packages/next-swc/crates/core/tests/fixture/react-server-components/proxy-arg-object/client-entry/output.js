@@ -0,0 +1,5 @@
+/* __next_internal_client_entry_do_not_use__ */ const { createProxy  } = require("private-next-rsc-mod-ref-proxy");
+module.exports = createProxy({
+    id: "/some-project/src/some-file.js",
+    name: "*"
+});
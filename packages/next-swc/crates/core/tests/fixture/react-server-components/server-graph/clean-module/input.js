@@ -0,0 +1,11 @@
+// This is a comment.
+
+import fs from "fs"
+
+import { format } from "./utils"
+
+"not-a-directive";
+
+export default function () {
+  return format(fs.readFileSync("data.txt"));
+}
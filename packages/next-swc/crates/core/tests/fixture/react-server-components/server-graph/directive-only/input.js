@@ -0,0 +1,2 @@
+// A client entry without any code.
+"use client";
@@ -0,0 +1,3 @@
+// A client entry without any code.
+/* __next_internal_client_entry_do_not_use__ */ const { createProxy  } = require("private-next-rsc-mod-ref-proxy");
+module.exports = createProxy("/some-project/src/some-file.js");
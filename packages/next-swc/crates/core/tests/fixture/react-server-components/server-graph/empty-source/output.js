@@ -0,0 +1,4 @@
+import '';
+export default function() {
+    return null;
+}
@@ -0,0 +1,9 @@
+// @next-rsc-ignore-file
+
+"use client";
+
+import "client-only"
+
+export default function () {
+  return null;
+}
@@ -0,0 +1,6 @@
+// @next-rsc-ignore-file
+"use client";
+import "client-only";
+export default function() {
+    return null;
+}
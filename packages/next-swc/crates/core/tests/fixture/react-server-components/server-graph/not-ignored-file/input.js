@@ -0,0 +1,7 @@
+// @next-rsc-dont-ignore-file
+
+"use client";
+
+export default function () {
+  return null;
+}
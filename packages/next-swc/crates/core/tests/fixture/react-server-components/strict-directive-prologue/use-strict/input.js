@@ -0,0 +1,7 @@
+"use strict";
+
+"use client";
+
+export default function () {
+  return null;
+}
@@ -58,6 +58,7 @@ fn test(input: &Path, minify: bool) {
                 is_development: true,
                 is_server: false,
                 server_components: None,
+                disable_deprecation_warnings: false,
                 styled_components: Some(assert_json("{}")),
                 remove_console: None,
                 react_remove_properties: None,
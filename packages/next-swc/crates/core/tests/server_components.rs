@@ -0,0 +1,1173 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use next_swc::react_server_components::{
+    check_imports, check_module, directive_kind, process_module, server_components,
+    server_components_with_analysis, Analysis, Change, Classification, Config, DirectiveKind,
+    EffectiveConfig, ImportKind, ModuleImports, Options, Preset, ProcessResult, Severity,
+};
+use once_cell::sync::Lazy;
+
+use swc_core::{
+    base::{try_with_handler, Compiler},
+    common::{
+        comments::{Comments, SingleThreadedComments},
+        errors::HANDLER,
+        sync::Lrc,
+        BytePos, FileName, FilePathMapping, SourceMap, Span, Spanned, SyntaxContext, DUMMY_SP,
+    },
+    ecma::ast::*,
+    ecma::parser::parse_file_as_module,
+    ecma::transforms::base::pass::noop,
+    ecma::visit::VisitMutWith,
+};
+
+static COMPILER: Lazy<Arc<Compiler>> = Lazy::new(|| {
+    let cm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+
+    Arc::new(Compiler::new(cm))
+});
+
+fn analyze(src: &str, options: Options) -> (bool, Analysis) {
+    let analysis: Rc<RefCell<Analysis>> = Default::default();
+    let fm = COMPILER.cm.new_source_file(
+        FileName::Real("/some-project/src/some-file.js".into()),
+        src.to_owned(),
+    );
+    let file_start_pos = fm.start_pos;
+    let ok = try_with_handler(COMPILER.cm.clone(), Default::default(), |handler| {
+        COMPILER.process_js_with_custom_pass(
+            fm,
+            None,
+            handler,
+            &Default::default(),
+            |_, comments| {
+                server_components_with_analysis(
+                    FileName::Real("/some-project/src/some-file.js".into()),
+                    file_start_pos,
+                    Config::WithOptions(options),
+                    comments.clone(),
+                    analysis.clone(),
+                )
+            },
+            |_, _| noop(),
+        )
+    })
+    .is_ok();
+    let analysis = analysis.borrow().clone();
+    (ok, analysis)
+}
+
+fn parse(cm: Lrc<SourceMap>, src: &str) -> Module {
+    parse_with_comments(cm, src, None)
+}
+
+fn parse_with_comments(cm: Lrc<SourceMap>, src: &str, comments: Option<&dyn Comments>) -> Module {
+    let fm = cm.new_source_file(
+        FileName::Real("/some-project/src/some-file.js".into()),
+        src.to_owned(),
+    );
+    parse_file_as_module(
+        &fm,
+        Default::default(),
+        EsVersion::latest(),
+        comments,
+        &mut vec![],
+    )
+    .unwrap()
+}
+
+// Parses and transforms `src`, returning the span of the parsed module along
+// with the transformed module.
+fn transform(src: &str, options: Options) -> (Span, Module) {
+    testing::run_test(false, |cm, handler| {
+        let mut module = parse(cm, src);
+        let span = module.span;
+        HANDLER.set(handler, || {
+            module.visit_mut_with(&mut server_components(
+                FileName::Real("/some-project/src/some-file.js".into()),
+                Config::WithOptions(options),
+                SingleThreadedComments::default(),
+            ))
+        });
+        Ok((span, module))
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_record_client_entry_rewrite() {
+    let (ok, analysis) = analyze(
+        r#""client";
+
+export default function Page() {
+  return null;
+}
+"#,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.changes.len(), 2);
+    assert!(matches!(
+        &analysis.changes[0],
+        Change::DirectiveRemoved { directive, .. } if &**directive == "client"
+    ));
+    assert_eq!(
+        analysis.changes[1],
+        Change::BodyReplaced {
+            old_len: 1,
+            new_len: 2
+        }
+    );
+}
+
+#[test]
+fn should_record_directive_removal_in_client_graph() {
+    let (ok, analysis) = analyze(
+        r#""client";
+
+export default function Page() {
+  return null;
+}
+"#,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.changes.len(), 1);
+    assert!(matches!(
+        &analysis.changes[0],
+        Change::DirectiveRemoved { directive, .. } if &**directive == "client"
+    ));
+}
+
+#[test]
+fn should_give_the_module_ref_the_module_span() {
+    let (span, module) = transform(
+        r#""client";
+
+export default function Page() {
+  return null;
+}
+"#,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+    assert!(!span.is_dummy());
+    assert_eq!(module.body.len(), 2);
+    match &module.body[0] {
+        ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => {
+            assert_eq!(var.span, span);
+            assert_eq!(var.decls[0].span, span);
+        }
+        item => panic!("expected the proxy require, got {:?}", item),
+    }
+    match &module.body[1] {
+        ModuleItem::Stmt(Stmt::Expr(expr)) => assert_eq!(expr.span, span),
+        item => panic!("expected the module.exports assignment, got {:?}", item),
+    }
+}
+
+fn check(src: &str) -> Analysis {
+    testing::run_test(false, |cm, handler| {
+        let comments = SingleThreadedComments::default();
+        let mut module = parse_with_comments(cm, src, Some(&comments));
+        Ok(HANDLER.set(handler, || {
+            check_module(
+                FileName::Real("/some-project/src/some-file.js".into()),
+                BytePos(0),
+                Default::default(),
+                comments.clone(),
+                &mut module,
+            )
+        }))
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_skip_ignored_files_when_checking_a_module() {
+    let analysis = check(
+        r#"// @next-rsc-ignore-file
+import "client-only"
+"#,
+    );
+    assert_eq!(analysis.classification, None);
+    assert!(analysis.diagnostics.is_empty());
+}
+
+#[test]
+fn should_check_each_module_against_its_own_graph() {
+    let server = check(
+        r#"import { useState } from 'react'
+
+export default function Page() {
+  return null;
+}
+"#,
+    );
+    assert_eq!(server.classification, Some(Classification::Module));
+    assert_eq!(server.diagnostics.len(), 1);
+    assert_eq!(server.diagnostics[0].severity, Severity::Error);
+    assert_eq!(
+        server.diagnostics[0].message,
+        "Disallowed React API `useState` in the Server Components compilation."
+    );
+
+    let client = check(
+        r#""use client";
+
+import "server-only"
+import { useState } from 'react'
+
+export default function Button() {
+  return null;
+}
+"#,
+    );
+    assert_eq!(client.classification, Some(Classification::ClientEntry));
+    assert_eq!(client.diagnostics.len(), 1);
+    assert_eq!(
+        client.diagnostics[0].message,
+        "Disallowed import of `server-only` in the Client Components compilation."
+    );
+    assert!(client
+        .changes
+        .iter()
+        .all(|change| !matches!(change, Change::BodyReplaced { .. })));
+
+    let clean = check(
+        r#"import { format } from './utils'
+
+export default function Page() {
+  return format(null);
+}
+"#,
+    );
+    assert_eq!(clean.classification, Some(Classification::Module));
+    assert!(clean.diagnostics.is_empty());
+}
+
+#[test]
+fn should_warn_about_duplicate_client_directives() {
+    let analysis = check(
+        r#""use client";
+"use client";
+
+export default function Button() {
+  return null;
+}
+"#,
+    );
+    assert_eq!(analysis.classification, Some(Classification::ClientEntry));
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(analysis.diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+        analysis.diagnostics[0].message,
+        "Duplicate `\"use client\"` directive, the module is already a client entry."
+    );
+    // The warning points at the second directive.
+    let removed: Vec<_> = analysis
+        .changes
+        .iter()
+        .filter_map(|change| match change {
+            Change::DirectiveRemoved { span, .. } => Some(*span),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(removed.len(), 2);
+    assert!(removed[1].contains(analysis.diagnostics[0].span));
+}
+
+#[test]
+fn should_report_imports_of_client_entry_sources() {
+    let (ok, analysis) = analyze(
+        r#"import Button from './button'
+import { format } from './utils'
+
+export default function Page() {
+  return format(Button);
+}
+"#,
+        Options {
+            is_server: true,
+            client_entry_sources: Some(vec!["./button".into()]),
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.classification, Some(Classification::Module));
+    assert_eq!(analysis.client_entry_imports.len(), 1);
+    assert_eq!(&*analysis.client_entry_imports[0].0, "./button");
+}
+
+#[test]
+fn should_not_attach_the_marker_at_a_dummy_module_span() {
+    testing::run_test(false, |cm, handler| {
+        let mut module = parse(
+            cm,
+            r#""use client";
+
+export default function Button() {
+  return null;
+}
+"#,
+        );
+        module.span = DUMMY_SP;
+        let first_stmt = module.body[1].span();
+
+        let comments = SingleThreadedComments::default();
+        HANDLER.set(handler, || {
+            module.visit_mut_with(&mut server_components(
+                FileName::Real("/some-project/src/some-file.js".into()),
+                Config::WithOptions(Options {
+                    is_server: true,
+                    ..Default::default()
+                }),
+                comments.clone(),
+            ))
+        });
+
+        assert!(!comments.has_leading(DUMMY_SP.lo));
+        let leading = comments.get_leading(first_stmt.lo).unwrap();
+        assert_eq!(leading.len(), 1);
+        assert_eq!(
+            &*leading[0].text,
+            " __next_internal_client_entry_do_not_use__ "
+        );
+        match &module.body[0] {
+            ModuleItem::Stmt(stmt) => assert_eq!(stmt.span(), first_stmt),
+            item => panic!("expected the proxy require, got {:?}", item),
+        }
+        Ok(())
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_collect_hook_usage() {
+    let (ok, analysis) = analyze(
+        r#""use client";
+
+import { useEffect, useState } from 'react'
+import { useRouter } from 'next/router'
+
+export default function Button() {
+  const [state] = useState(0);
+  useEffect(() => {}, [state]);
+  return useRouter();
+}
+"#,
+        Options {
+            is_server: false,
+            collect_hook_usage: true,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    let hooks: Vec<&str> = analysis.hooks.iter().map(|hook| &**hook).collect();
+    assert_eq!(hooks, vec!["useEffect", "useState"]);
+}
+
+#[test]
+fn should_not_collect_hook_usage_by_default() {
+    let (ok, analysis) = analyze(
+        r#"import { useState } from 'react'
+"#,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert!(analysis.hooks.is_empty());
+}
+
+#[test]
+fn should_recognize_directives() {
+    assert_eq!(directive_kind("use client"), Some(DirectiveKind::Client));
+    assert_eq!(directive_kind("client"), Some(DirectiveKind::Client));
+    assert_eq!(directive_kind("use server"), Some(DirectiveKind::Server));
+
+    assert_eq!(directive_kind("use strict"), None);
+    assert_eq!(directive_kind("server"), None);
+    assert_eq!(directive_kind("Use client"), None);
+    assert_eq!(directive_kind(" use client"), None);
+    assert_eq!(directive_kind(""), None);
+}
+
+#[test]
+fn should_mark_client_entries_with_both_spellings_of_the_directive() {
+    for directive in ["use client", "client"] {
+        for is_server in [true, false] {
+            let (ok, analysis) = analyze(
+                &format!("\"{}\";\n\nexport default function () {{}}\n", directive),
+                Options {
+                    is_server,
+                    ..Default::default()
+                },
+            );
+            assert!(ok);
+            assert_eq!(
+                analysis.classification,
+                Some(Classification::ClientEntry),
+                "{}",
+                directive
+            );
+        }
+    }
+}
+
+#[test]
+fn should_only_report_the_disallowed_specifiers() {
+    testing::run_test(false, |cm, handler| {
+        let mut module = parse(
+            cm.clone(),
+            r#"import { useState, cn } from 'react'
+
+export default function Page() {
+  return cn;
+}
+"#,
+        );
+        let analysis: Rc<RefCell<Analysis>> = Default::default();
+        HANDLER.set(handler, || {
+            module.visit_mut_with(&mut server_components_with_analysis(
+                FileName::Real("/some-project/src/some-file.js".into()),
+                BytePos(0),
+                Config::WithOptions(Options {
+                    is_server: true,
+                    ..Default::default()
+                }),
+                SingleThreadedComments::default(),
+                analysis.clone(),
+            ))
+        });
+
+        let analysis = analysis.take();
+        assert_eq!(analysis.diagnostics.len(), 1);
+        assert_eq!(
+            analysis.diagnostics[0].message,
+            "Disallowed React API `useState` in the Server Components compilation."
+        );
+        assert_eq!(
+            cm.span_to_snippet(analysis.diagnostics[0].span).unwrap(),
+            "useState"
+        );
+
+        // The import is only diagnosed, not rewritten.
+        match &module.body[0] {
+            ModuleItem::ModuleDecl(ModuleDecl::Import(import)) => {
+                assert_eq!(import.specifiers.len(), 2)
+            }
+            item => panic!("expected the react import, got {:?}", item),
+        }
+        Ok(())
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_check_an_external_import_list() {
+    let config = EffectiveConfig::new(
+        FileName::Real("/some-project/src/some-file.js".into()),
+        Default::default(),
+    );
+    let imports = vec![
+        ModuleImports {
+            source: ("server-only".into(), DUMMY_SP),
+            kind: ImportKind::SideEffect,
+            specifiers: vec![],
+        },
+        ModuleImports {
+            source: ("react".into(), DUMMY_SP),
+            kind: ImportKind::Named,
+            specifiers: vec![
+                (
+                    "useState".into(),
+                    DUMMY_SP,
+                    ("useState".into(), SyntaxContext::empty()),
+                ),
+                (
+                    "useId".into(),
+                    DUMMY_SP,
+                    ("useId".into(), SyntaxContext::empty()),
+                ),
+            ],
+        },
+    ];
+
+    let server: Vec<String> = check_imports(&imports, true, &config)
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+    assert_eq!(
+        server,
+        vec!["Disallowed React API `useState` in the Server Components compilation."]
+    );
+
+    let client: Vec<String> = check_imports(&imports, false, &config)
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+    assert_eq!(
+        client,
+        vec!["Disallowed import of `server-only` in the Client Components compilation."]
+    );
+}
+
+#[test]
+fn should_not_resolve_the_sources_of_an_external_import_list_again() {
+    let config = EffectiveConfig::new(
+        FileName::Real("/some-project/src/some-file.js".into()),
+        Options {
+            alias_map: Some(
+                vec![
+                    ("@/server-only".into(), "server-only".into()),
+                    ("server-only".into(), "some-module".into()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+    // `@/server-only` resolved through `alias_map`.
+    let imports = vec![ModuleImports {
+        source: ("server-only".into(), DUMMY_SP),
+        kind: ImportKind::SideEffect,
+        specifiers: vec![],
+    }];
+
+    let client: Vec<String> = check_imports(&imports, false, &config)
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect();
+    assert_eq!(
+        client,
+        vec!["Disallowed import of `server-only` in the Client Components compilation."]
+    );
+}
+
+#[test]
+fn should_keep_the_option_errors_apart_from_the_checked_imports() {
+    let config = EffectiveConfig::new(
+        FileName::Real("/some-project/src/some-file.js".into()),
+        Options {
+            banned_source_patterns: Some(vec!["(".into()]),
+            fail_fast: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(config.errors().len(), 1);
+    assert!(config.errors()[0]
+        .message
+        .starts_with("Invalid pattern `(` in `bannedSourcePatterns`"));
+
+    let imports = vec![ModuleImports {
+        source: ("server-only".into(), DUMMY_SP),
+        kind: ImportKind::SideEffect,
+        specifiers: vec![],
+    }];
+    for _ in 0..2 {
+        let client: Vec<String> = check_imports(&imports, false, &config)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(
+            client,
+            vec!["Disallowed import of `server-only` in the Client Components compilation."]
+        );
+    }
+}
+
+#[test]
+fn should_note_the_replaced_statements_in_verbose_mode() {
+    let (ok, analysis) = analyze(
+        r#""use client";
+
+import { useState } from 'react'
+
+export function Counter() {
+  return useState(0);
+}
+
+export default function Button() {
+  return null;
+}
+"#,
+        Options {
+            is_server: true,
+            verbose: true,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(analysis.diagnostics[0].severity, Severity::Note);
+    assert_eq!(
+        analysis.diagnostics[0].message,
+        "Replaced the 3 top level statements and declarations of the client entry with the module \
+         reference."
+    );
+}
+
+#[test]
+fn should_report_invalid_banned_source_patterns() {
+    let (ok, analysis) = analyze(
+        r#"export default function Page() {
+  return null;
+}
+"#,
+        Options {
+            is_server: true,
+            banned_source_patterns: Some(vec!["^@internal/(".into(), "^legacy-".into()]),
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(analysis.diagnostics[0].severity, Severity::Error);
+    assert!(analysis.diagnostics[0]
+        .message
+        .starts_with("Invalid pattern `^@internal/(` in `bannedSourcePatterns`"));
+}
+
+#[test]
+fn should_report_an_invalid_proxy_prelude() {
+    let (ok, analysis) = analyze(
+        r#""use client";
+
+export default function () {}
+"#,
+        Options {
+            is_server: true,
+            proxy_prelude: Some(vec!["console.log(".into(), "console.log(1);".into()]),
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(analysis.diagnostics[0].severity, Severity::Error);
+    assert!(analysis.diagnostics[0]
+        .message
+        .starts_with("Invalid statements `console.log(` in `proxyPrelude`"));
+}
+
+#[test]
+fn should_include_the_byte_range_of_diagnostics() {
+    testing::run_test(false, |cm, handler| {
+        // Another file first, so that the positions in the source map don't
+        // start at the beginning of the file.
+        cm.new_source_file(FileName::Anon, "import 'server-only'".into());
+        let src = r#"import { format } from './utils'
+import "client-only"
+"#;
+        let fm = cm.new_source_file(
+            FileName::Real("/some-project/src/some-file.js".into()),
+            src.into(),
+        );
+        let mut module = parse_file_as_module(
+            &fm,
+            Default::default(),
+            EsVersion::latest(),
+            None,
+            &mut vec![],
+        )
+        .unwrap();
+        let analysis = HANDLER.set(handler, || {
+            check_module(
+                FileName::Real("/some-project/src/some-file.js".into()),
+                fm.start_pos,
+                Default::default(),
+                SingleThreadedComments::default(),
+                &mut module,
+            )
+        });
+
+        assert_eq!(analysis.diagnostics.len(), 1);
+        let diagnostic = &analysis.diagnostics[0];
+        assert_eq!((diagnostic.start, diagnostic.end), (33, 53));
+        assert_eq!(&src[33..53], "import \"client-only\"");
+        Ok(())
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_classify_client_entries_in_the_client_graph() {
+    let (ok, analysis) = analyze(
+        r#""use client";
+
+import { useState } from 'react'
+
+export default function Counter() {
+  return useState(0);
+}
+"#,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.classification, Some(Classification::ClientEntry));
+    assert!(analysis.diagnostics.is_empty());
+    assert!(analysis
+        .changes
+        .iter()
+        .all(|change| !matches!(change, Change::BodyReplaced { .. })));
+}
+
+#[test]
+fn should_stop_after_the_first_diagnostic_in_fail_fast_mode() {
+    let src = r#"import "client-only";
+import { useState } from "react";
+import { flushSync } from "react-dom";
+
+export default function () {
+  return useState(0);
+}
+"#;
+    let (ok, analysis) = analyze(
+        src,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    assert!(analysis.diagnostics.len() > 1);
+
+    let (ok, analysis) = analyze(
+        src,
+        Options {
+            is_server: true,
+            fail_fast: true,
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert!(analysis.diagnostics[0].message.contains("`client-only`"));
+}
+
+#[test]
+fn should_match_directives_regardless_of_quotes_and_escapes() {
+    for directive in [r#"'use client'"#, r#""use client""#, r#""use cli\u0065nt""#] {
+        let (ok, analysis) = analyze(
+            &format!("{};\n\nexport default function () {{}}\n", directive),
+            Options {
+                is_server: false,
+                ..Default::default()
+            },
+        );
+        assert!(ok, "{}", directive);
+        assert_eq!(
+            analysis.classification,
+            Some(Classification::ClientEntry),
+            "{}",
+            directive
+        );
+    }
+}
+
+#[test]
+fn should_rename_create_proxy_if_the_prelude_uses_it() {
+    let (_, module) = transform(
+        r#""use client";
+
+export default function () {}
+"#,
+        Options {
+            is_server: true,
+            proxy_prelude: Some(vec!["function createProxy() {}".into()]),
+            ..Default::default()
+        },
+    );
+
+    let binding = module
+        .body
+        .iter()
+        .find_map(|item| match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => match &var.decls[0].name {
+                Pat::Object(ObjectPat { props, .. }) => match &props[0] {
+                    ObjectPatProp::KeyValue(KeyValuePatProp { value, .. }) => match &**value {
+                        Pat::Ident(ident) => Some(ident.id.sym.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .expect("createProxy should be destructured to a renamed binding");
+    assert_eq!(&*binding, "createProxy1");
+}
+
+#[test]
+fn should_tell_if_a_server_module_is_pure() {
+    let (ok, analysis) = analyze(
+        r#"import { cache } from "react";
+
+export default function () {
+  return cache;
+}
+"#,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert!(analysis.pure);
+
+    let (ok, analysis) = analyze(
+        r#"import "client-only";
+
+export default function () {}
+"#,
+        Options {
+            is_server: true,
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    assert!(!analysis.pure);
+}
+
+#[test]
+fn should_seed_the_ban_lists_with_the_preset() {
+    let server_src = r#"import "client-only";
+import "react-dom/server";
+import { useState } from "react";
+import { flushSync } from "react-dom";
+"#;
+    let client_src = "import \"server-only\";\n";
+    let messages = |src: &str, is_server: bool, preset: Option<Preset>| {
+        let (_, analysis) = analyze(
+            src,
+            Options {
+                is_server,
+                preset,
+                ..Default::default()
+            },
+        );
+        analysis
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect::<Vec<_>>()
+    };
+
+    for preset in [None, Some(Preset::NextAppRouter)] {
+        assert_eq!(
+            messages(server_src, true, preset),
+            vec![
+                "Disallowed import of `client-only` in the Server Components compilation.",
+                "Disallowed import of `react-dom/server` in the Server Components compilation.",
+                "Disallowed React API `useState` in the Server Components compilation.",
+                "Disallowed ReactDOM API `flushSync` in the Server Components compilation.",
+            ]
+        );
+        assert_eq!(
+            messages(client_src, false, preset),
+            vec!["Disallowed import of `server-only` in the Client Components compilation."]
+        );
+    }
+
+    assert_eq!(
+        messages(server_src, true, Some(Preset::Minimal)),
+        vec!["Disallowed import of `client-only` in the Server Components compilation."]
+    );
+    assert_eq!(
+        messages(client_src, false, Some(Preset::Minimal)),
+        vec!["Disallowed import of `server-only` in the Client Components compilation."]
+    );
+}
+
+#[test]
+fn should_override_the_preset_with_explicit_ban_lists() {
+    let (_, analysis) = analyze(
+        r#"import "client-only";
+import "some-module";
+"#,
+        Options {
+            is_server: true,
+            preset: Some(Preset::Minimal),
+            invalid_server_imports: Some(vec!["some-module".into()]),
+            ..Default::default()
+        },
+    );
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(
+        analysis.diagnostics[0].message,
+        "Disallowed import of `some-module` in the Server Components compilation."
+    );
+}
+
+#[test]
+fn should_classify_a_module_with_only_the_directive() {
+    let src = "// A client entry without any code.\n\"use client\";\n";
+    let (ok, analysis) = analyze(
+        src,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.classification, Some(Classification::ClientEntry));
+
+    let (_, module) = transform(
+        src,
+        Options {
+            is_server: false,
+            ..Default::default()
+        },
+    );
+    assert!(module.body.is_empty());
+}
+
+#[test]
+fn should_check_imports_after_resolving_aliases() {
+    let (ok, analysis) = analyze(
+        r#"import "@/dom-server";
+import "client-only";
+"#,
+        Options {
+            is_server: true,
+            alias_map: Some(
+                vec![
+                    ("@/dom-server".into(), "react-dom/server".into()),
+                    ("client-only".into(), "some-module".into()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(
+        analysis.diagnostics[0].message,
+        "Disallowed import of `react-dom/server` in the Server Components compilation."
+    );
+}
+
+#[test]
+fn should_warn_about_nested_client_boundaries() {
+    let src = r#""use client";
+
+import Button from "./button";
+
+export default function () {
+  return Button;
+}
+"#;
+    let options = || Options {
+        is_server: false,
+        client_entry_sources: Some(vec!["./button".into()]),
+        ..Default::default()
+    };
+
+    let (ok, analysis) = analyze(src, options());
+    assert!(ok);
+    assert!(analysis.diagnostics.is_empty());
+
+    let (ok, analysis) = analyze(
+        src,
+        Options {
+            warn_nested_client_boundary: true,
+            ..options()
+        },
+    );
+    assert!(ok);
+    assert_eq!(analysis.diagnostics.len(), 1);
+    assert_eq!(analysis.diagnostics[0].severity, Severity::Warning);
+    assert!(analysis.diagnostics[0]
+        .message
+        .starts_with("Import of the client entry `./button` by another client entry."));
+}
+
+fn process(src: &str, is_server: bool) -> (ProcessResult, Module) {
+    testing::run_test(false, |cm, handler| {
+        let comments = SingleThreadedComments::default();
+        let mut module = parse_with_comments(cm, src, Some(&comments));
+        let result = HANDLER.set(handler, || {
+            process_module(
+                &mut module,
+                FileName::Real("/some-project/src/some-file.js".into()),
+                BytePos(0),
+                Options {
+                    is_server,
+                    ..Default::default()
+                },
+                comments.clone(),
+            )
+        });
+        Ok((result, module))
+    })
+    .unwrap()
+}
+
+#[test]
+fn should_process_a_client_entry() {
+    let (result, _) = process(
+        r#""use client";
+
+export default function () {}
+"#,
+        true,
+    );
+    assert_eq!(result.classification, Some(Classification::ClientEntry));
+    assert!(result.diagnostics.is_empty());
+    assert!(result.rewritten);
+}
+
+#[test]
+fn should_process_a_clean_server_module() {
+    let (result, _) = process(
+        r#"import { cache } from "react";
+
+export default function () {
+  return cache;
+}
+"#,
+        true,
+    );
+    assert_eq!(result.classification, Some(Classification::Module));
+    assert!(result.diagnostics.is_empty());
+    assert!(!result.rewritten);
+}
+
+#[test]
+fn should_process_a_server_module_with_a_violation() {
+    let (result, _) = process(
+        r#"import "client-only";
+
+export default function () {}
+"#,
+        true,
+    );
+    assert_eq!(result.classification, Some(Classification::Module));
+    assert_eq!(result.diagnostics.len(), 1);
+    assert_eq!(result.diagnostics[0].severity, Severity::Error);
+    assert!(!result.rewritten);
+}
+
+#[test]
+fn should_not_stop_at_a_warning_in_fail_fast_mode() {
+    let (ok, analysis) = analyze(
+        r#"import "heavy-lib";
+import "server-only";
+import "server-only";
+"#,
+        Options {
+            is_server: false,
+            discouraged_client_imports: Some(vec!["heavy-lib".into()]),
+            fail_fast: true,
+            ..Default::default()
+        },
+    );
+    assert!(!ok);
+    let severities: Vec<_> = analysis
+        .diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.severity)
+        .collect();
+    assert_eq!(severities, vec![Severity::Warning, Severity::Error]);
+}
+
+#[test]
+fn should_leave_ignored_files_alone_when_processing_a_module() {
+    let src = r#"// @next-rsc-ignore-file
+"use client";
+
+import "server-only";
+
+export default function () {}
+"#;
+    let (result, module) = process(src, true);
+    assert_eq!(result.classification, None);
+    assert!(result.diagnostics.is_empty());
+    assert!(!result.rewritten);
+    assert_eq!(module.body.len(), 3);
+}
+
+#[test]
+fn should_only_export_the_marker_from_the_esm_proxy() {
+    let src = r#""use client";
+
+export default function () {}
+"#;
+    let (_, module) = transform(
+        src,
+        Options {
+            is_server: true,
+            esm: true,
+            marker_as_export: true,
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        module.body.last(),
+        Some(ModuleItem::ModuleDecl(ModuleDecl::ExportDecl(_)))
+    ));
+
+    for lazy in [false, true] {
+        let (ok, analysis) = analyze(
+            src,
+            Options {
+                is_server: true,
+                lazy,
+                marker_as_export: true,
+                ..Default::default()
+            },
+        );
+        assert!(!ok);
+        assert_eq!(analysis.diagnostics.len(), 1);
+        assert!(analysis.diagnostics[0]
+            .message
+            .starts_with("`markerAsExport` requires `esm`"));
+    }
+}
+
+#[test]
+fn should_rename_the_lazy_proxy_if_the_prelude_uses_it() {
+    let (_, module) = transform(
+        r#""use client";
+
+export default function () {}
+"#,
+        Options {
+            is_server: true,
+            lazy: true,
+            proxy_prelude: Some(vec!["let proxy;".into()]),
+            ..Default::default()
+        },
+    );
+
+    let bindings: Vec<_> = module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => match &var.decls[0].name {
+                Pat::Ident(ident) => Some(ident.id.sym.to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bindings, vec!["proxy", "proxy1"]);
+}